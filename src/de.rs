@@ -0,0 +1,384 @@
+// de.rs
+//
+// Copyright (c) 2019-2026  Minnesota Department of Transportation
+//
+//! Deserialization of feature tags with serde.
+//!
+use crate::error::{Error, Result};
+use crate::tile::FeatureView;
+use crate::vector_tile::tile::Value;
+use serde::Deserialize;
+use serde::de::value::BorrowedStrDeserializer;
+use serde::de::{self, DeserializeSeed, IntoDeserializer, Unexpected, Visitor};
+use std::fmt::Display;
+
+impl de::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        Error::Serde(msg.to_string())
+    }
+}
+
+impl<'a> FeatureView<'a> {
+    /// Deserialize feature tags into a struct or map.
+    ///
+    /// Numeric values are coerced into the target type, if they are in
+    /// range.  Floats are only coerced into integers when they have no
+    /// fractional part.
+    ///
+    /// Returns an error for missing keys or type mismatches.
+    ///
+    /// # Example
+    /// ```
+    /// # use mvt::{Error, GeomEncoder, GeomType, Tile};
+    /// # use serde::Deserialize;
+    /// # fn main() -> Result<(), Error> {
+    /// #[derive(Deserialize)]
+    /// struct Road {
+    ///     name: String,
+    ///     lanes: u8,
+    /// }
+    /// # let mut tile = Tile::new(4096);
+    /// # let layer = tile.create_layer("roads");
+    /// # let geom_data = GeomEncoder::new(GeomType::Point)
+    /// #     .point(1.0, 2.0)?
+    /// #     .encode()?;
+    /// # let mut feature = layer.into_feature(geom_data);
+    /// # feature.add_tag_string("name", "Main St");
+    /// # feature.add_tag_int("lanes", 2);
+    /// # tile.add_layer(feature.into_layer())?;
+    /// # let data = tile.to_bytes()?;
+    /// let tile = Tile::from_bytes(&data)?;
+    /// for feature in tile.features("roads") {
+    ///     let road: Road = feature.properties()?;
+    ///     assert_eq!(road.lanes, 2);
+    /// }
+    /// # Ok(()) }
+    /// ```
+    pub fn properties<T>(&self) -> Result<T>
+    where
+        T: Deserialize<'a>,
+    {
+        T::deserialize(TagsDeserializer { feature: *self })
+    }
+}
+
+/// Deserializer for all tags of a feature
+struct TagsDeserializer<'a> {
+    /// Feature view
+    feature: FeatureView<'a>,
+}
+
+impl<'de> de::Deserializer<'de> for TagsDeserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_map(TagsAccess {
+            tags: Box::new(self.feature.tags()),
+            key: "",
+            value: None,
+        })
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+/// Map access for feature tags
+struct TagsAccess<'a> {
+    /// Remaining tags
+    tags: Box<dyn Iterator<Item = (&'a str, &'a Value)> + 'a>,
+
+    /// Current key
+    key: &'a str,
+
+    /// Current value
+    value: Option<&'a Value>,
+}
+
+impl<'de> de::MapAccess<'de> for TagsAccess<'de> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        match self.tags.next() {
+            Some((key, value)) => {
+                self.key = key;
+                self.value = Some(value);
+                seed.deserialize(BorrowedStrDeserializer::new(key))
+                    .map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let value = self
+            .value
+            .take()
+            .ok_or_else(|| Error::Serde("value without key".to_string()))?;
+        let key = self.key;
+        seed.deserialize(ValueDeserializer { value })
+            .map_err(|e| match e {
+                Error::Serde(msg) => {
+                    Error::Serde(format!("tag `{key}`: {msg}"))
+                }
+                e => e,
+            })
+    }
+}
+
+/// Deserializer for one tag value
+struct ValueDeserializer<'a> {
+    /// Tag value
+    value: &'a Value,
+}
+
+impl ValueDeserializer<'_> {
+    /// Get value as an integer, if it is a float with no fractional part
+    fn float_integer(&self) -> Option<f64> {
+        let v = match (self.value.float_value, self.value.double_value) {
+            (Some(v), _) => f64::from(v),
+            (None, Some(v)) => v,
+            (None, None) => return None,
+        };
+        (v.fract() == 0.0).then_some(v)
+    }
+}
+
+/// 2^63, as a float (exclusive upper bound of i64)
+const TWO_POW_63: f64 = 9_223_372_036_854_775_808.0;
+
+/// 2^64, as a float (exclusive upper bound of u64)
+const TWO_POW_64: f64 = 18_446_744_073_709_551_616.0;
+
+/// Deserialize integer types, coercing floats with no fractional part.
+///
+/// Floats outside the range of i64 / u64 are rejected, rather than
+/// saturated.  Range checks for the target type are done by the visitor.
+macro_rules! deserialize_integer {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value>
+            where
+                V: Visitor<'de>,
+            {
+                match self.float_integer() {
+                    Some(v) if (-TWO_POW_63..0.0).contains(&v) => {
+                        visitor.visit_i64(v as i64)
+                    }
+                    Some(v) if (0.0..TWO_POW_64).contains(&v) => {
+                        visitor.visit_u64(v as u64)
+                    }
+                    Some(v) => Err(de::Error::invalid_value(
+                        Unexpected::Float(v),
+                        &visitor,
+                    )),
+                    None => self.deserialize_any(visitor),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let value = self.value;
+        if let Some(v) = &value.string_value {
+            visitor.visit_borrowed_str(v)
+        } else if let Some(v) = value.float_value {
+            visitor.visit_f32(v)
+        } else if let Some(v) = value.double_value {
+            visitor.visit_f64(v)
+        } else if let Some(v) = value.int_value {
+            visitor.visit_i64(v)
+        } else if let Some(v) = value.uint_value {
+            visitor.visit_u64(v)
+        } else if let Some(v) = value.sint_value {
+            visitor.visit_i64(v)
+        } else if let Some(v) = value.bool_value {
+            visitor.visit_bool(v)
+        } else {
+            visitor.visit_unit()
+        }
+    }
+
+    deserialize_integer! {
+        deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if *self.value == Value::default() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match &self.value.string_value {
+            Some(v) => visitor.visit_enum(v.as_str().into_deserializer()),
+            None => self.deserialize_any(visitor),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i128 u128 f32 f64 char str string bytes byte_buf unit
+        unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::encoder::{GeomEncoder, GeomType};
+    use crate::tile::Tile;
+    use std::collections::HashMap;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    #[serde(rename_all = "lowercase")]
+    enum Surface {
+        Paved,
+        Gravel,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Road<'a> {
+        name: &'a str,
+        lanes: u8,
+        offset: i32,
+        speed: f32,
+        oneway: bool,
+        surface: Surface,
+        width: Option<f64>,
+    }
+
+    fn road_tile() -> Tile {
+        let mut tile = Tile::new(4096);
+        let layer = tile.create_layer("roads");
+        let geom_data = GeomEncoder::new(GeomType::Point)
+            .point(1.0, 2.0)
+            .unwrap()
+            .encode()
+            .unwrap();
+        let mut feature = layer.into_feature(geom_data);
+        feature.add_tag_string("name", "Main St");
+        feature.add_tag_double("lanes", 2.0);
+        feature.add_tag_sint("offset", -3);
+        feature.add_tag_uint("speed", 35);
+        feature.add_tag_bool("oneway", true);
+        feature.add_tag_string("surface", "gravel");
+        tile.add_layer(feature.into_layer()).unwrap();
+        Tile::from_bytes(&tile.to_bytes().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_properties() {
+        let tile = road_tile();
+        let feature = tile.features("roads").next().unwrap();
+        let road: Road = feature.properties().unwrap();
+        assert_eq!(
+            road,
+            Road {
+                name: "Main St",
+                lanes: 2,
+                offset: -3,
+                speed: 35.0,
+                oneway: true,
+                surface: Surface::Gravel,
+                width: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_properties_errors() {
+        #[derive(Debug, Deserialize)]
+        struct Missing {
+            _color: String,
+        }
+        let tile = road_tile();
+        let feature = tile.features("roads").next().unwrap();
+        let err = feature.properties::<Missing>().unwrap_err();
+        assert_eq!(err.to_string(), "Serde error: missing field `_color`");
+        let err = feature.properties::<HashMap<&str, u8>>().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Serde error: tag `name`: invalid type: string \"Main St\", \
+            expected u8"
+        );
+    }
+
+    #[test]
+    fn test_properties_range() {
+        #[derive(Debug, Deserialize)]
+        struct Int<T> {
+            _v: T,
+        }
+        let mut tile = Tile::new(4096);
+        let mut layer = tile.create_layer("values");
+        for (v, sint) in [(-1.0, -1), (1.5, 0), (1e30, 300), (-1e30, 0)] {
+            let geom_data = GeomEncoder::new(GeomType::Point)
+                .point(1.0, 2.0)
+                .unwrap()
+                .encode()
+                .unwrap();
+            let mut feature = layer.into_feature(geom_data);
+            feature.add_tag_double("_v", v);
+            feature.add_tag_sint("_s", sint);
+            layer = feature.into_layer();
+        }
+        tile.add_layer(layer).unwrap();
+        let features: Vec<_> = tile.features("values").collect();
+        assert_eq!(features[0].properties::<Int<i8>>().unwrap()._v, -1);
+        assert!(features[0].properties::<Int<u64>>().is_err());
+        assert!(features[1].properties::<Int<u8>>().is_err());
+        assert!(features[2].properties::<Int<u64>>().is_err());
+        assert!(features[2].properties::<Int<f64>>().is_ok());
+        assert!(features[3].properties::<Int<i64>>().is_err());
+        #[derive(Debug, Deserialize)]
+        struct Sint {
+            _s: u8,
+        }
+        assert!(features[0].properties::<Sint>().is_err());
+        assert!(features[2].properties::<Sint>().is_err());
+    }
+}
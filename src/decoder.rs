@@ -0,0 +1,194 @@
+// decoder.rs
+//
+// Copyright (c) 2019-2026  Minnesota Department of Transportation
+//
+//! Decoder for Mapbox Vector Tile (MVT) geometry.
+//!
+use crate::encoder::{Command, CommandInt, GeomType, ParamInt};
+use crate::error::{Error, Result};
+
+/// Path of points in tile coördinates
+pub(crate) type Path = Vec<(i32, i32)>;
+
+/// Decoder for encoded geometry data
+struct GeomDecoder<'a> {
+    /// Geometry type
+    geom_tp: GeomType,
+
+    /// Layer version
+    version: u32,
+
+    /// Encoded geometry data
+    data: &'a [u32],
+
+    /// Offset of next value in data
+    offset: usize,
+
+    /// Cursor position
+    cursor: (i32, i32),
+
+    /// Decoded paths
+    paths: Vec<Path>,
+}
+
+impl<'a> GeomDecoder<'a> {
+    /// Create a new geometry decoder
+    fn new(geom_tp: GeomType, version: u32, data: &'a [u32]) -> Self {
+        GeomDecoder {
+            geom_tp,
+            version,
+            data,
+            offset: 0,
+            cursor: (0, 0),
+            paths: Vec::new(),
+        }
+    }
+
+    /// Get the next value
+    fn next_value(&mut self) -> Result<u32> {
+        let val = self
+            .data
+            .get(self.offset)
+            .copied()
+            .ok_or(Error::InvalidGeometry())?;
+        self.offset += 1;
+        Ok(val)
+    }
+
+    /// Decode the next point, relative to the cursor
+    fn next_point(&mut self) -> Result<(i32, i32)> {
+        let dx = ParamInt::decode(self.next_value()?).value;
+        let dy = ParamInt::decode(self.next_value()?).value;
+        let (x, y) = self.cursor;
+        self.cursor = (x.wrapping_add(dx), y.wrapping_add(dy));
+        Ok(self.cursor)
+    }
+
+    /// Decode all commands into paths
+    fn decode(mut self) -> Result<Vec<Path>> {
+        while self.offset < self.data.len() {
+            let code = self.next_value()?;
+            let cmd =
+                CommandInt::try_decode(code).ok_or(Error::InvalidGeometry())?;
+            match (cmd.id, self.geom_tp) {
+                (Command::MoveTo, GeomType::Point) => {
+                    let mut path = Vec::with_capacity(cmd.count as usize);
+                    for _ in 0..cmd.count {
+                        path.push(self.next_point()?);
+                    }
+                    self.paths.push(path);
+                }
+                (Command::MoveTo, _) => {
+                    if cmd.count != 1 {
+                        return Err(Error::InvalidGeometry());
+                    }
+                    let pt = self.next_point()?;
+                    self.paths.push(vec![pt]);
+                }
+                (Command::LineTo, GeomType::Linestring | GeomType::Polygon) => {
+                    for _ in 0..cmd.count {
+                        let pt = self.next_point()?;
+                        self.paths
+                            .last_mut()
+                            .ok_or(Error::InvalidGeometry())?
+                            .push(pt);
+                    }
+                }
+                // version 1 has no ClosePath semantics
+                (
+                    Command::ClosePath,
+                    GeomType::Linestring | GeomType::Polygon,
+                ) if self.version == 1 => {}
+                (Command::ClosePath, GeomType::Polygon) => {
+                    if cmd.count != 1 || self.paths.is_empty() {
+                        return Err(Error::InvalidGeometry());
+                    }
+                }
+                _ => return Err(Error::InvalidGeometry()),
+            }
+        }
+        Ok(self.paths)
+    }
+}
+
+/// Decode geometry data into paths (in tile coördinates).
+///
+/// * `geom_tp` Geometry type.
+/// * `data` Encoded geometry data.
+pub(crate) fn decode_paths(
+    geom_tp: GeomType,
+    data: &[u32],
+) -> Result<Vec<Path>> {
+    decode_paths_version(geom_tp, 2, data)
+}
+
+/// Decode geometry data of a layer version into paths.
+///
+/// * `geom_tp` Geometry type.
+/// * `version` Layer version; ClosePath commands are not checked for
+///   version 1.
+/// * `data` Encoded geometry data.
+pub(crate) fn decode_paths_version(
+    geom_tp: GeomType,
+    version: u32,
+    data: &[u32],
+) -> Result<Vec<Path>> {
+    GeomDecoder::new(geom_tp, version, data).decode()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Examples from MVT spec:
+    #[test]
+    fn test_point() {
+        let paths = decode_paths(GeomType::Point, &[9, 50, 34]).unwrap();
+        assert_eq!(paths, vec![vec![(25, 17)]]);
+    }
+
+    #[test]
+    fn test_multipoint() {
+        let paths = decode_paths(GeomType::Point, &[17, 10, 14, 3, 9]).unwrap();
+        assert_eq!(paths, vec![vec![(5, 7), (3, 2)]]);
+    }
+
+    #[test]
+    fn test_multilinestring() {
+        let data = [9, 4, 4, 18, 0, 16, 16, 0, 9, 17, 17, 10, 4, 8];
+        let paths = decode_paths(GeomType::Linestring, &data).unwrap();
+        assert_eq!(
+            paths,
+            vec![vec![(2, 2), (2, 10), (10, 10)], vec![(1, 1), (3, 5)]]
+        );
+    }
+
+    #[test]
+    fn test_polygon() {
+        let data = [9, 6, 12, 18, 10, 12, 24, 44, 15];
+        let paths = decode_paths(GeomType::Polygon, &data).unwrap();
+        assert_eq!(paths, vec![vec![(3, 6), (8, 12), (20, 34)]]);
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(decode_paths(GeomType::Point, &[9, 50]).is_err());
+        assert!(decode_paths(GeomType::Linestring, &[18, 0, 16]).is_err());
+        assert!(decode_paths(GeomType::Linestring, &[15]).is_err());
+        assert!(decode_paths(GeomType::Polygon, &[3]).is_err());
+        assert!(
+            decode_paths(GeomType::Linestring, &[9, 2, 2, 10, 10, 0, 15])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_version_1() {
+        let data = [9, 2, 2, 10, 10, 0, 15];
+        let paths =
+            decode_paths_version(GeomType::Linestring, 1, &data).unwrap();
+        assert_eq!(paths, vec![vec![(1, 1), (6, 1)]]);
+        assert!(decode_paths_version(GeomType::Polygon, 1, &[15]).is_ok());
+        assert!(decode_paths_version(GeomType::Point, 1, &[15]).is_err());
+    }
+}
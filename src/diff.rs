@@ -0,0 +1,514 @@
+// diff.rs
+//
+// Copyright (c) 2019-2026  Minnesota Department of Transportation
+//
+//! Structural diff between two tiles.
+//!
+use crate::decoder::{Path, decode_paths_version};
+use crate::error::{Error, Result};
+use crate::tile::{Tile, feature_geom_type};
+use crate::vector_tile::tile::{Feature as VtFeature, Layer as VtLayer, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// Differences between two tiles.
+///
+/// Created with [Tile::diff](struct.Tile.html#method.diff).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TileDiff {
+    /// Names of added layers
+    pub added_layers: Vec<String>,
+    /// Names of removed layers
+    pub removed_layers: Vec<String>,
+    /// Changed layers (present in both tiles)
+    pub layers: Vec<LayerDiff>,
+}
+
+/// Key for matching features between two versions of a layer.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum FeatureKey {
+    /// Feature ID
+    Id(u64),
+    /// Position among features without an ID
+    Index(usize),
+}
+
+/// Differences between two versions of a layer.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LayerDiff {
+    /// Layer name
+    pub name: String,
+    /// Feature counts (before, after)
+    pub num_features: (usize, usize),
+    /// Keys of added features
+    pub added_ids: Vec<FeatureKey>,
+    /// Keys of removed features
+    pub removed_ids: Vec<FeatureKey>,
+    /// Changed tags of matched features
+    pub tag_changes: Vec<TagChange>,
+    /// Changed geometry of matched features
+    pub geom_changes: Vec<GeomChange>,
+}
+
+/// Changed tag of a feature.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TagChange {
+    /// Feature key
+    pub id: FeatureKey,
+    /// Tag key
+    pub key: String,
+    /// Value before (formatted), or `None` if added
+    pub before: Option<String>,
+    /// Value after (formatted), or `None` if removed
+    pub after: Option<String>,
+}
+
+/// Changed geometry of a feature.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GeomChange {
+    /// Feature key
+    pub id: FeatureKey,
+    /// Maximum distance between matching vertices (tile units), or `None`
+    /// if the geometry type or number of vertices changed
+    pub distance: Option<f64>,
+}
+
+/// Format a tag value
+fn fmt_value(value: &Value) -> String {
+    if let Some(v) = &value.string_value {
+        format!("{v:?}")
+    } else if let Some(v) = value.float_value {
+        v.to_string()
+    } else if let Some(v) = value.double_value {
+        v.to_string()
+    } else if let Some(v) = value.int_value.or(value.sint_value) {
+        v.to_string()
+    } else if let Some(v) = value.uint_value {
+        v.to_string()
+    } else if let Some(v) = value.bool_value {
+        v.to_string()
+    } else {
+        "null".to_string()
+    }
+}
+
+/// Get tags of a feature, independent of key / value ordering
+fn feature_tags(
+    layer: &VtLayer,
+    feature: &VtFeature,
+) -> BTreeMap<String, String> {
+    feature
+        .tags
+        .chunks_exact(2)
+        .filter_map(|kv| {
+            let key = layer.keys.get(kv[0] as usize)?;
+            let val = layer.values.get(kv[1] as usize)?;
+            Some((key.clone(), fmt_value(val)))
+        })
+        .collect()
+}
+
+/// Get features of a layer by key (first feature for duplicate IDs)
+fn features_by_key(layer: &VtLayer) -> HashMap<FeatureKey, &VtFeature> {
+    let mut features = HashMap::new();
+    let mut index = 0;
+    for feature in &layer.features {
+        let key = match feature.id {
+            Some(id) => FeatureKey::Id(id),
+            None => {
+                index += 1;
+                FeatureKey::Index(index - 1)
+            }
+        };
+        features.entry(key).or_insert(feature);
+    }
+    features
+}
+
+/// Get sorted keys
+fn sorted_ids<'a>(
+    ids: impl Iterator<Item = &'a FeatureKey>,
+) -> Vec<FeatureKey> {
+    let mut ids: Vec<FeatureKey> = ids.copied().collect();
+    ids.sort_unstable();
+    ids
+}
+
+/// Decode feature paths, scaled to a target extent
+fn scaled_paths(
+    layer: &VtLayer,
+    feature: &VtFeature,
+    extent: u32,
+) -> Result<Vec<Vec<(f64, f64)>>> {
+    let scale = f64::from(extent) / f64::from(layer.extent.unwrap_or(4096));
+    let scale_path = |path: Path| {
+        path.into_iter()
+            .map(|(x, y)| (f64::from(x) * scale, f64::from(y) * scale))
+            .collect()
+    };
+    let Some(geom_tp) = feature_geom_type(feature) else {
+        return Ok(Vec::new());
+    };
+    Ok(
+        decode_paths_version(geom_tp, layer.version, &feature.geometry)?
+            .into_iter()
+            .map(scale_path)
+            .collect(),
+    )
+}
+
+/// Get maximum distance between matching vertices of two features.
+///
+/// Returns `None` if the geometry type or structure is different.
+fn geom_distance(
+    layer0: &VtLayer,
+    f0: &VtFeature,
+    layer1: &VtLayer,
+    f1: &VtFeature,
+) -> Result<Option<f64>> {
+    if feature_geom_type(f0) != feature_geom_type(f1) {
+        return Ok(None);
+    }
+    let extent = layer0.extent.unwrap_or(4096);
+    let paths0 = scaled_paths(layer0, f0, extent)?;
+    let paths1 = scaled_paths(layer1, f1, extent)?;
+    if paths0.len() != paths1.len()
+        || paths0.iter().zip(&paths1).any(|(a, b)| a.len() != b.len())
+    {
+        return Ok(None);
+    }
+    let dist = paths0
+        .iter()
+        .flatten()
+        .zip(paths1.iter().flatten())
+        .map(|(a, b)| (a.0 - b.0).hypot(a.1 - b.1))
+        .fold(0.0, f64::max);
+    Ok(Some(dist))
+}
+
+/// Compare two versions of a layer
+fn diff_layer(
+    layer0: &VtLayer,
+    layer1: &VtLayer,
+    tolerance: f64,
+) -> Result<LayerDiff> {
+    let features0 = features_by_key(layer0);
+    let features1 = features_by_key(layer1);
+    let mut diff = LayerDiff {
+        name: layer0.name.clone(),
+        num_features: (layer0.features.len(), layer1.features.len()),
+        added_ids: sorted_ids(
+            features1.keys().filter(|id| !features0.contains_key(id)),
+        ),
+        removed_ids: sorted_ids(
+            features0.keys().filter(|id| !features1.contains_key(id)),
+        ),
+        ..Default::default()
+    };
+    for id in sorted_ids(features0.keys()) {
+        let Some(f1) = features1.get(&id) else {
+            continue;
+        };
+        let f0 = features0[&id];
+        let mut tags0 = feature_tags(layer0, f0);
+        for (key, after) in feature_tags(layer1, f1) {
+            let before = tags0.remove(&key);
+            if before.as_ref() != Some(&after) {
+                diff.tag_changes.push(TagChange {
+                    id,
+                    key,
+                    before,
+                    after: Some(after),
+                });
+            }
+        }
+        for (key, before) in tags0 {
+            diff.tag_changes.push(TagChange {
+                id,
+                key,
+                before: Some(before),
+                after: None,
+            });
+        }
+        let distance = geom_distance(layer0, f0, layer1, f1)?;
+        if distance.is_none_or(|d| d > tolerance) {
+            diff.geom_changes.push(GeomChange { id, distance });
+        }
+    }
+    diff.tag_changes
+        .sort_by(|a, b| (a.id, &a.key).cmp(&(b.id, &b.key)));
+    Ok(diff)
+}
+
+impl fmt::Display for FeatureKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FeatureKey::Id(id) => write!(f, "{id}"),
+            FeatureKey::Index(index) => write!(f, "#{index}"),
+        }
+    }
+}
+
+impl LayerDiff {
+    /// Check if there are no differences.
+    pub fn is_empty(&self) -> bool {
+        self.num_features.0 == self.num_features.1
+            && self.added_ids.is_empty()
+            && self.removed_ids.is_empty()
+            && self.tag_changes.is_empty()
+            && self.geom_changes.is_empty()
+    }
+}
+
+impl TileDiff {
+    /// Check if there are no differences.
+    pub fn is_empty(&self) -> bool {
+        self.added_layers.is_empty()
+            && self.removed_layers.is_empty()
+            && self.layers.is_empty()
+    }
+}
+
+impl fmt::Display for TileDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for name in &self.added_layers {
+            writeln!(f, "+ layer {name:?}")?;
+        }
+        for name in &self.removed_layers {
+            writeln!(f, "- layer {name:?}")?;
+        }
+        for layer in &self.layers {
+            let (n0, n1) = layer.num_features;
+            writeln!(f, "~ layer {:?}: {n0} -> {n1} features", layer.name)?;
+            for id in &layer.added_ids {
+                writeln!(f, "  + feature {id}")?;
+            }
+            for id in &layer.removed_ids {
+                writeln!(f, "  - feature {id}")?;
+            }
+            for tag in &layer.tag_changes {
+                let before = tag.before.as_deref().unwrap_or("(none)");
+                let after = tag.after.as_deref().unwrap_or("(none)");
+                writeln!(
+                    f,
+                    "  ~ feature {}: tag {:?}: {before} -> {after}",
+                    tag.id, tag.key
+                )?;
+            }
+            for geom in &layer.geom_changes {
+                match geom.distance {
+                    Some(d) => writeln!(
+                        f,
+                        "  ~ feature {}: geometry moved {d:.2}",
+                        geom.id
+                    )?,
+                    None => writeln!(
+                        f,
+                        "  ~ feature {}: geometry changed",
+                        geom.id
+                    )?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Tile {
+    /// Compare with another tile.
+    ///
+    /// * `other` Tile to compare (after).
+    /// * `tolerance` Maximum vertex distance for unchanged geometry (in tile
+    ///   units of this tile's layer).
+    ///
+    /// Layers are matched by name, and features by ID.  Features without an
+    /// ID are matched by position among the other features without an ID
+    /// (`#0`, `#1`, etc.).  If a layer has duplicate IDs, only the first
+    /// feature with each ID is compared; the others only affect the feature
+    /// count.  Tags are compared by key and value, so the ordering of layer
+    /// keys and values does not matter.  Geometry of layers with different
+    /// extents is scaled before comparing.
+    ///
+    /// Returns an error if `tolerance` is negative or not finite, or a
+    /// matched feature contains invalid geometry.
+    ///
+    /// # Example
+    /// ```
+    /// # use mvt::{Error, Tile};
+    /// # fn main() -> Result<(), Error> {
+    /// let mut tile = Tile::new(4096);
+    /// tile.add_layer(tile.create_layer("roads"))?;
+    /// let diff = tile.diff(&Tile::new(4096), 0.0)?;
+    /// assert_eq!(diff.removed_layers, ["roads"]);
+    /// # Ok(()) }
+    /// ```
+    pub fn diff(&self, other: &Tile, tolerance: f64) -> Result<TileDiff> {
+        if !tolerance.is_finite() || tolerance < 0.0 {
+            return Err(Error::InvalidValue());
+        }
+        let layers0 = self.all_layers()?;
+        let layers1 = other.all_layers()?;
+        let mut diff = TileDiff::default();
+        for layer0 in &layers0 {
+            match layers1.iter().find(|l| l.name == layer0.name) {
+                Some(layer1) => {
+                    let layer = diff_layer(layer0, layer1, tolerance)?;
+                    if !layer.is_empty() {
+                        diff.layers.push(layer);
+                    }
+                }
+                None => diff.removed_layers.push(layer0.name.clone()),
+            }
+        }
+        for layer1 in &layers1 {
+            if !layers0.iter().any(|l| l.name == layer1.name) {
+                diff.added_layers.push(layer1.name.clone());
+            }
+        }
+        Ok(diff)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::encoder::{GeomEncoder, GeomType};
+
+    fn make_tile(tags: &[(&str, &str)], x: f64, extra: bool) -> Tile {
+        let mut tile = Tile::new(4096);
+        let mut layer = tile.create_layer("roads");
+        // add an unused value, to change value table ordering
+        let mut feature = layer.into_feature(
+            GeomEncoder::new(GeomType::Point)
+                .point(0.0, 0.0)
+                .unwrap()
+                .encode()
+                .unwrap(),
+        );
+        feature.set_id(99);
+        for (key, val) in tags.iter().rev() {
+            feature.add_tag_string(key, val);
+        }
+        layer = feature.into_layer();
+        let geom_data = GeomEncoder::new(GeomType::Linestring)
+            .point(x, 0.0)
+            .unwrap()
+            .point(100.0, 100.0)
+            .unwrap()
+            .encode()
+            .unwrap();
+        let mut feature = layer.into_feature(geom_data);
+        feature.set_id(1);
+        for (key, val) in tags {
+            feature.add_tag_string(key, val);
+        }
+        layer = feature.into_layer();
+        if extra {
+            let geom_data = GeomEncoder::new(GeomType::Point)
+                .point(5.0, 5.0)
+                .unwrap()
+                .encode()
+                .unwrap();
+            let mut feature = layer.into_feature(geom_data);
+            feature.set_id(2);
+            layer = feature.into_layer();
+        }
+        tile.add_layer(layer).unwrap();
+        tile
+    }
+
+    #[test]
+    fn test_same() {
+        let a = make_tile(&[("name", "A"), ("kind", "road")], 0.0, false);
+        let b = make_tile(&[("kind", "road"), ("name", "A")], 1.0, false);
+        let diff = a.diff(&b, 1.0).unwrap();
+        assert!(diff.is_empty(), "{diff}");
+        for tolerance in [-1.0, f64::NAN, f64::INFINITY] {
+            assert!(a.diff(&b, tolerance).is_err());
+        }
+    }
+
+    #[test]
+    fn test_changes() {
+        let a = make_tile(&[("name", "A"), ("kind", "road")], 0.0, false);
+        let mut b = make_tile(&[("name", "B")], 10.0, true);
+        b.add_layer(b.create_layer("water")).unwrap();
+        let diff = a.diff(&b, 1.0).unwrap();
+        assert_eq!(diff.added_layers, ["water"]);
+        assert!(diff.removed_layers.is_empty());
+        let layer = &diff.layers[0];
+        assert_eq!(layer.num_features, (2, 3));
+        assert_eq!(layer.added_ids, [FeatureKey::Id(2)]);
+        assert_eq!(
+            layer.tag_changes,
+            [
+                TagChange {
+                    id: FeatureKey::Id(1),
+                    key: "kind".to_string(),
+                    before: Some("\"road\"".to_string()),
+                    after: None,
+                },
+                TagChange {
+                    id: FeatureKey::Id(1),
+                    key: "name".to_string(),
+                    before: Some("\"A\"".to_string()),
+                    after: Some("\"B\"".to_string()),
+                },
+                TagChange {
+                    id: FeatureKey::Id(99),
+                    key: "kind".to_string(),
+                    before: Some("\"road\"".to_string()),
+                    after: None,
+                },
+                TagChange {
+                    id: FeatureKey::Id(99),
+                    key: "name".to_string(),
+                    before: Some("\"A\"".to_string()),
+                    after: Some("\"B\"".to_string()),
+                },
+            ]
+        );
+        assert_eq!(
+            layer.geom_changes,
+            [GeomChange {
+                id: FeatureKey::Id(1),
+                distance: Some(10.0),
+            }]
+        );
+        assert!(
+            diff.to_string()
+                .contains("~ feature 1: geometry moved 10.00")
+        );
+    }
+
+    #[test]
+    fn test_no_ids() {
+        let make_tile = |xs: &[f64]| {
+            let mut tile = Tile::new(4096);
+            let mut layer = tile.create_layer("points");
+            for x in xs {
+                let geom_data = GeomEncoder::new(GeomType::Point)
+                    .point(*x, 0.0)
+                    .unwrap()
+                    .encode()
+                    .unwrap();
+                layer = layer.into_feature(geom_data).into_layer();
+            }
+            tile.add_layer(layer).unwrap();
+            tile
+        };
+        let a = make_tile(&[0.0, 10.0]);
+        assert!(a.diff(&make_tile(&[0.0, 10.0]), 0.0).unwrap().is_empty());
+        let diff = a.diff(&make_tile(&[0.0, 20.0, 30.0]), 0.0).unwrap();
+        let layer = &diff.layers[0];
+        assert_eq!(layer.added_ids, [FeatureKey::Index(2)]);
+        assert_eq!(
+            layer.geom_changes,
+            [GeomChange {
+                id: FeatureKey::Index(1),
+                distance: Some(10.0),
+            }]
+        );
+        assert!(diff.to_string().contains("  + feature #2\n"));
+    }
+}
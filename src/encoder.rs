@@ -4,12 +4,14 @@
 //
 //! Encoder for Mapbox Vector Tile (MVT) geometry.
 //!
+use crate::decoder::{Path, decode_paths};
 use crate::error::{Error, Result};
 use pointy::{BBox, Float, Pt, Seg, Transform};
+use std::sync::OnceLock;
 
 /// Path commands
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
-enum Command {
+pub(crate) enum Command {
     /// Move to new position
     MoveTo = 1,
 
@@ -22,19 +24,19 @@ enum Command {
 
 /// Integer command
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
-struct CommandInt {
+pub(crate) struct CommandInt {
     /// Path command
-    id: Command,
+    pub(crate) id: Command,
 
     /// Command count
-    count: u32,
+    pub(crate) count: u32,
 }
 
 /// Integer parameter
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
-struct ParamInt {
+pub(crate) struct ParamInt {
     /// Parameter value
-    value: i32,
+    pub(crate) value: i32,
 }
 
 /// Geometry types for [Features](struct.Feature.html).
@@ -51,6 +53,44 @@ pub enum GeomType {
     Polygon,
 }
 
+/// Snapping mode for quantizing coördinates to tile units.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Snap {
+    /// Round to nearest, with halfway values rounded away from zero
+    #[default]
+    Round,
+
+    /// Round down
+    Floor,
+
+    /// Round up
+    Ceil,
+
+    /// Round to nearest, with halfway values always rounded up (toward
+    /// positive infinity), and keep collinear points in polygon rings.
+    ///
+    /// Each encoder only snaps its own points: a vertex shared by adjacent
+    /// features is snapped identically only if it has exactly the same input
+    /// coördinates in each feature.  Collinear vertices are not removed, so
+    /// such shared vertices are never simplified away from one feature but
+    /// not another.
+    Topology,
+}
+
+impl Snap {
+    /// Snap a value to an integer
+    fn snap<F: Float>(self, v: F) -> F {
+        match self {
+            Snap::Round => v.round(),
+            Snap::Floor => v.floor(),
+            Snap::Ceil => v.ceil(),
+            Snap::Topology => {
+                (v + F::from(0.5).unwrap_or_else(F::zero)).floor()
+            }
+        }
+    }
+}
+
 /// Encoder for [Feature](struct.Feature.html) geometry.
 ///
 /// This can consist of Point, Linestring or Polygon data.
@@ -74,9 +114,15 @@ where
     /// Geometry type
     geom_tp: GeomType,
 
+    /// Layer version
+    version: u32,
+
     /// X,Y position at end of linestring/polygon geometry
     xy_end: Option<Pt<F>>,
 
+    /// X,Y position at start of polygon ring
+    xy_start: Option<Pt<F>>,
+
     /// Transform to MVT coordinates
     transform: Transform<F>,
 
@@ -109,11 +155,101 @@ where
 
     /// Encoded geometry data
     data: Vec<u32>,
+
+    /// Buffer around bounding box for clipping points (tile units)
+    buffer: i32,
+
+    /// Snapping mode
+    snap: Snap,
+
+    /// Snapping grid size (tile units)
+    grid: u32,
+
+    /// Minimum polygon ring area (square tile units)
+    min_area: f64,
+
+    /// Minimum linestring length (tile units)
+    min_length: f64,
+
+    /// Keep a representative point when all geometry is dropped
+    keep_point: bool,
+
+    /// Tile points of current linestring / polygon ring
+    part: Vec<(i32, i32)>,
+
+    /// Data offset at start of current part
+    part_offset: usize,
+
+    /// Tile point before start of current part
+    part_cursor: Option<(i32, i32)>,
+
+    /// Size and representative point of largest dropped part
+    dropped: Option<(f64, (i32, i32))>,
+
+    /// Count of input points
+    input_points: usize,
+
+    /// Flag indicating geometry was clipped
+    clipped: bool,
+
+    /// Measures (M values)
+    measures: VertexValues,
+
+    /// Elevations (Z values)
+    #[cfg(feature = "mvt3")]
+    elevations: VertexValues,
+
+    /// Count of tile vertices
+    vertices: usize,
+
+    /// Count of tile vertices before start of current part
+    part_vertices: usize,
+
+    /// Polygon ring points (for geozero input)
+    #[cfg(feature = "geozero")]
+    ring: Vec<(F, F)>,
+
+    /// Count of rings in current polygon (for geozero input)
+    #[cfg(feature = "geozero")]
+    ring_count: usize,
+}
+
+/// Values associated with each tile vertex, such as measures
+struct VertexValues {
+    /// Value of current input point (NaN if missing)
+    current: f64,
+
+    /// Value at end of linestring / polygon geometry
+    end: f64,
+
+    /// Value at start of polygon ring
+    start: f64,
+
+    /// Values of each tile vertex (if any were added)
+    values: Option<Vec<f64>>,
+}
+
+/// Bounding box in tile coördinates.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TileBounds {
+    /// Minimum X value
+    x_min: i32,
+
+    /// Minimum Y value
+    y_min: i32,
+
+    /// Maximum X value
+    x_max: i32,
+
+    /// Maximum Y value
+    y_max: i32,
 }
 
 /// Validated geometry data for [Feature](struct.Feature.html)s.
 ///
-/// Use [GeomEncoder](struct.GeomEncoder.html) to encode.
+/// Use [GeomEncoder](struct.GeomEncoder.html) to encode.  Metrics (such as
+/// [bounds](#method.bounds) and [area](#method.area)) decode the data once,
+/// on first use.
 ///
 /// # Example
 /// ```
@@ -132,6 +268,99 @@ pub struct GeomData {
 
     /// Encoded geometry data
     data: Vec<u32>,
+
+    /// Decoded paths (for metrics)
+    paths: OnceLock<Vec<Path>>,
+
+    /// Count of input points
+    input_points: usize,
+
+    /// Flag indicating geometry was clipped
+    clipped: bool,
+
+    /// Measures of each vertex
+    measures: Option<Vec<f64>>,
+
+    /// Elevations of each vertex
+    #[cfg(feature = "mvt3")]
+    elevations: Option<Vec<f64>>,
+}
+
+impl Default for VertexValues {
+    fn default() -> Self {
+        VertexValues {
+            current: f64::NAN,
+            end: f64::NAN,
+            start: f64::NAN,
+            values: None,
+        }
+    }
+}
+
+impl VertexValues {
+    /// Set the value of the current input point.
+    ///
+    /// * `value` Value (NaN if missing).
+    /// * `vertices` Count of previous tile vertices.
+    fn set(&mut self, value: f64, vertices: usize) {
+        if self.values.is_none() && !value.is_nan() {
+            self.values = Some(vec![f64::NAN; vertices]);
+        }
+        self.current = value;
+    }
+
+    /// Set value at end of the current input segment
+    fn set_end(&mut self) {
+        self.end = self.current;
+    }
+
+    /// Set value at start of the current polygon ring
+    fn set_start(&mut self) {
+        self.start = self.current;
+    }
+
+    /// Restore the current value to the start of the polygon ring
+    fn restore_start(&mut self) {
+        self.current = self.start;
+    }
+
+    /// Interpolate along the current input segment, replacing the current
+    /// value.  Returns the replaced value.
+    ///
+    /// * `t` Position along segment (0 to 1).
+    fn interpolate(&mut self, t: f64) -> f64 {
+        let value = self.current;
+        self.current = self.end + (value - self.end) * t;
+        value
+    }
+
+    /// Push the current value for a new tile vertex
+    fn push(&mut self) {
+        if let Some(values) = &mut self.values {
+            values.push(self.current);
+        }
+    }
+
+    /// Set the current value to that of a previous tile vertex
+    fn repeat(&mut self, vertex: usize) {
+        if let Some(values) = &self.values {
+            self.current = values.get(vertex).copied().unwrap_or(f64::NAN);
+        }
+    }
+
+    /// Pop the value of the most recent tile vertex
+    fn pop(&mut self) {
+        if let Some(values) = &mut self.values {
+            values.pop();
+        }
+    }
+
+    /// Truncate to a count of tile vertices
+    fn truncate(&mut self, vertices: usize) {
+        if let Some(values) = &mut self.values {
+            values.truncate(vertices);
+        }
+    }
 }
 
 impl CommandInt {
@@ -148,14 +377,22 @@ impl CommandInt {
 
     /// Decode command
     fn decode(code: u32) -> Self {
+        match Self::try_decode(code) {
+            Some(cmd) => cmd,
+            None => panic!("Invalid code: {code}"),
+        }
+    }
+
+    /// Decode command, checking for invalid codes
+    pub(crate) fn try_decode(code: u32) -> Option<Self> {
         let id = match code & 0x7 {
             1 => Command::MoveTo,
             2 => Command::LineTo,
             7 => Command::ClosePath,
-            _ => panic!("Invalid code: {code}"),
+            _ => return None,
         };
         let count = code >> 3;
-        CommandInt { id, count }
+        Some(CommandInt { id, count })
     }
 }
 
@@ -169,6 +406,12 @@ impl ParamInt {
     fn encode(&self) -> u32 {
         ((self.value << 1) ^ (self.value >> 31)) as u32
     }
+
+    /// Decode a parameter
+    pub(crate) fn decode(code: u32) -> Self {
+        let value = ((code >> 1) as i32) ^ -((code & 1) as i32);
+        ParamInt { value }
+    }
 }
 
 impl<F> GeomEncoder<F>
@@ -181,6 +424,7 @@ where
     pub fn new(geom_tp: GeomType) -> Self {
         GeomEncoder {
             geom_tp,
+            version: 2,
             x_min: i32::MIN,
             x_max: i32::MAX,
             y_min: i32::MIN,
@@ -189,6 +433,31 @@ where
         }
     }
 
+    /// Create a new encoder with the same settings, but another geometry
+    /// type.
+    ///
+    /// * `geom_tp` Geometry type.
+    #[cfg(any(feature = "geozero", feature = "wkb"))]
+    pub(crate) fn with_geom_type(&self, geom_tp: GeomType) -> Self {
+        GeomEncoder {
+            geom_tp,
+            version: self.version,
+            transform: self.transform,
+            bbox: self.bbox,
+            x_min: self.x_min,
+            x_max: self.x_max,
+            y_min: self.y_min,
+            y_max: self.y_max,
+            buffer: self.buffer,
+            snap: self.snap,
+            grid: self.grid,
+            min_area: self.min_area,
+            min_length: self.min_length,
+            keep_point: self.keep_point,
+            ..Default::default()
+        }
+    }
+
     /// Adjust min/max values
     fn adjust_minmax(mut self) -> Self {
         if self.bbox != BBox::default() {
@@ -206,6 +475,34 @@ where
         self
     }
 
+    /// Set the layer version.
+    ///
+    /// * `version` Version of the layer (default 2).
+    ///
+    /// Version 1 layers have no winding order requirements, so polygon rings
+    /// from WKB or geozero input are encoded in their original order.  They
+    /// also have no ClosePath semantics, so each polygon ring is closed with
+    /// an explicit point (followed by ClosePath).
+    ///
+    /// Returns an error unless `version` is 1 or 2.
+    pub fn version(mut self, version: u32) -> Result<Self> {
+        match version {
+            1 | 2 => {
+                self.version = version;
+                Ok(self)
+            }
+            _ => Err(Error::WrongVersion()),
+        }
+    }
+
+    /// Set the version of an existing layer.
+    ///
+    /// Layers other than version 1 are encoded with version 2 rules.
+    pub(crate) fn layer_version(mut self, version: u32) -> Self {
+        self.version = if version == 1 { 1 } else { 2 };
+        self
+    }
+
     /// Add a bounding box
     pub fn bbox(mut self, bbox: BBox<F>) -> Self {
         self.bbox = bbox;
@@ -218,6 +515,66 @@ where
         self.adjust_minmax()
     }
 
+    /// Set buffer for clipping points.
+    ///
+    /// * `buffer` Buffer around bounding box (tile units).
+    ///
+    /// Points outside the bounding box plus buffer are omitted.  If all points
+    /// are omitted, the encoded geometry [is empty].
+    ///
+    /// [is empty]: struct.GeomData.html#method.is_empty
+    pub fn buffer(mut self, buffer: u32) -> Self {
+        self.buffer = i32::try_from(buffer).unwrap_or(i32::MAX);
+        self
+    }
+
+    /// Set the snapping mode.
+    ///
+    /// * `snap` Mode for quantizing coördinates to tile units.
+    pub fn snap(mut self, snap: Snap) -> Self {
+        self.snap = snap;
+        self
+    }
+
+    /// Set the snapping grid.
+    ///
+    /// * `grid` Grid size (tile units).  Coördinates are snapped to
+    ///   multiples of this value, which can be useful at low zoom levels.
+    pub fn grid(mut self, grid: u32) -> Self {
+        self.grid = grid;
+        self
+    }
+
+    /// Set minimum area of polygon rings.
+    ///
+    /// * `area` Minimum area (square tile units).
+    ///
+    /// Rings with a smaller area are omitted, as are rings with zero area.
+    pub fn min_area(mut self, area: F) -> Self {
+        self.min_area = area.to_f64().unwrap_or(0.0);
+        self
+    }
+
+    /// Set minimum length of linestrings.
+    ///
+    /// * `length` Minimum length (tile units).
+    ///
+    /// Linestrings which are shorter are omitted.
+    pub fn min_length(mut self, length: F) -> Self {
+        self.min_length = length.to_f64().unwrap_or(0.0);
+        self
+    }
+
+    /// Keep a representative point if all geometry is omitted.
+    ///
+    /// * `keep` If true, [encode](#method.encode) produces `Point` geometry
+    ///   at the center of the largest omitted ring or linestring, instead of
+    ///   empty geometry (default false).
+    pub fn keep_point(mut self, keep: bool) -> Self {
+        self.keep_point = keep;
+        self
+    }
+
     /// Push a Command
     fn push_command(&mut self, cmd: Command) {
         log::trace!("push_command: {cmd:?}");
@@ -241,7 +598,14 @@ where
         self.data.push(ParamInt::new(x.saturating_sub(px)).encode());
         self.data.push(ParamInt::new(y.saturating_sub(py)).encode());
         self.pt1 = Some((x, y));
+        if self.geom_tp != GeomType::Point {
+            self.part.push((x, y));
+        }
         self.count += 1;
+        self.vertices += 1;
+        self.measures.push();
+        #[cfg(feature = "mvt3")]
+        self.elevations.push();
     }
 
     /// Pop most recent point.
@@ -250,7 +614,12 @@ where
         self.data.pop();
         self.data.pop();
         self.pt1 = self.pt0;
+        self.part.pop();
         self.count -= 1;
+        self.vertices -= 1;
+        self.measures.pop();
+        #[cfg(feature = "mvt3")]
+        self.elevations.pop();
     }
 
     /// Add a point, taking ownership (for method chaining).
@@ -261,10 +630,86 @@ where
 
     /// Add a point.
     pub fn add_point(&mut self, x: F, y: F) -> Result<()> {
+        self.add_point_m(x, y, f64::NAN)
+    }
+
+    /// Add a point with a measure, taking ownership (for method chaining).
+    pub fn point_m(mut self, x: F, y: F, m: f64) -> Result<Self> {
+        self.add_point_m(x, y, m)?;
+        Ok(self)
+    }
+
+    /// Add a point with a measure (M value).
+    ///
+    /// * `m` Measure, such as speed or time along a track.
+    ///
+    /// Measures are interpolated for boundary points added by clipping.
+    /// They are encoded in a feature extension field, using the layer's
+    /// [measure_scale](struct.Layer.html#method.measure_scale).
+    pub fn add_point_m(&mut self, x: F, y: F, m: f64) -> Result<()> {
+        #[cfg(feature = "mvt3")]
+        self.elevations.set(f64::NAN, self.vertices);
+        self.measures.set(m, self.vertices);
+        self.add_input_point(x, y)
+    }
+
+    /// Add a point with an elevation (Z value).
+    ///
+    /// * `z` Elevation.
+    #[cfg(feature = "mvt3")]
+    pub(crate) fn add_point_elevation(
+        &mut self,
+        x: F,
+        y: F,
+        z: f64,
+    ) -> Result<()> {
+        self.elevations.set(z, self.vertices);
+        self.measures.set(f64::NAN, self.vertices);
+        self.add_input_point(x, y)
+    }
+
+    /// Add an input point, after setting its vertex values.
+    ///
+    /// Vertex values are interpolated for any boundary points added by
+    /// clipping.
+    fn add_input_point(&mut self, x: F, y: F) -> Result<()> {
+        self.input_points += 1;
+        if self.geom_tp == GeomType::Polygon && self.xy_start.is_none() {
+            self.xy_start = Some(Pt::from((x, y)));
+            self.measures.set_start();
+            #[cfg(feature = "mvt3")]
+            self.elevations.set_start();
+        }
         self.add_boundary_points(x, y)?;
         self.add_tile_point(x, y)
     }
 
+    /// Add a boundary point, with interpolated vertex values
+    fn add_boundary_point(
+        &mut self,
+        p0: Pt<F>,
+        p1: Pt<F>,
+        pt: Pt<F>,
+    ) -> Result<()> {
+        self.clipped = true;
+        let len = p0.distance(p1).to_f64().unwrap_or(0.0);
+        let t = if len > 0.0 {
+            p0.distance(pt).to_f64().unwrap_or(0.0) / len
+        } else {
+            0.0
+        };
+        let m = self.measures.interpolate(t);
+        #[cfg(feature = "mvt3")]
+        let z = self.elevations.interpolate(t);
+        let res = self.add_tile_point(pt.x, pt.y);
+        self.measures.current = m;
+        #[cfg(feature = "mvt3")]
+        {
+            self.elevations.current = z;
+        }
+        res
+    }
+
     /// Add one or two boundary points (if needed).
     fn add_boundary_points(&mut self, x: F, y: F) -> Result<()> {
         if let Some(pxy) = self.xy_end {
@@ -272,16 +717,19 @@ where
             let seg = Seg::new(pxy, xy);
             if let Some(seg) = seg.clip(self.bbox) {
                 if seg.p0 != pxy {
-                    self.add_tile_point(seg.p0.x, seg.p0.y)?;
+                    self.add_boundary_point(pxy, xy, seg.p0)?;
                 }
                 if seg.p1 != xy {
-                    self.add_tile_point(seg.p1.x, seg.p1.y)?;
+                    self.add_boundary_point(pxy, xy, seg.p1)?;
                 }
             }
         }
         match self.geom_tp {
             GeomType::Linestring | GeomType::Polygon => {
                 self.xy_end = Some(Pt::from((x, y)));
+                self.measures.set_end();
+                #[cfg(feature = "mvt3")]
+                self.elevations.set_end();
             }
             _ => (),
         }
@@ -290,7 +738,25 @@ where
 
     /// Add a tile point.
     fn add_tile_point(&mut self, x: F, y: F) -> Result<()> {
-        let pt = self.make_point(x, y)?;
+        let pt = self.snap_point(x, y)?;
+        let pt = if self.geom_tp == GeomType::Point {
+            if !self.is_within_buffer(pt) {
+                log::trace!("clipped point: {},{}", pt.0, pt.1);
+                self.clipped = true;
+                return Ok(());
+            }
+            pt
+        } else {
+            let clamped = self.clamp_point(pt);
+            self.clipped |= clamped != pt;
+            clamped
+        };
+        if self.count == 0 && self.geom_tp != GeomType::Point {
+            self.part.clear();
+            self.part_offset = self.data.len();
+            self.part_cursor = self.pt1;
+            self.part_vertices = self.vertices;
+        }
         if let Some((px, py)) = self.pt1
             && pt.0 == px
             && pt.1 == py
@@ -300,6 +766,7 @@ where
                 // multipolygon) is the same as the last of the previous line,
                 // we skip the MoveTo command and increase the count so the
                 // next point correctly gets a LineTo.
+                self.part.push((px, py));
                 self.count += 1;
             } else {
                 // Redundant points other than the first are unexpected, and
@@ -325,7 +792,10 @@ where
                     1 => self.push_command(Command::LineTo),
                     _ => (),
                 }
-                if self.count >= 2 && self.should_simplify_point(pt.0, pt.1) {
+                if self.count >= 2
+                    && self.snap != Snap::Topology
+                    && self.should_simplify_point(pt.0, pt.1)
+                {
                     self.pop_point();
                 }
             }
@@ -334,13 +804,33 @@ where
         Ok(())
     }
 
-    /// Make point with tile coörindates.
-    fn make_point(&self, x: F, y: F) -> Result<(i32, i32)> {
+    /// Clamp a tile point to bounding box.
+    fn clamp_point(&self, (x, y): (i32, i32)) -> (i32, i32) {
+        let x = x.clamp(self.x_min, self.x_max);
+        let y = y.clamp(self.y_min, self.y_max);
+        (x, y)
+    }
+
+    /// Check if a tile point is within bounding box plus buffer.
+    fn is_within_buffer(&self, (x, y): (i32, i32)) -> bool {
+        let buffer = self.buffer;
+        x >= self.x_min.saturating_sub(buffer)
+            && x <= self.x_max.saturating_add(buffer)
+            && y >= self.y_min.saturating_sub(buffer)
+            && y <= self.y_max.saturating_add(buffer)
+    }
+
+    /// Snap a point to tile coörindates.
+    fn snap_point(&self, x: F, y: F) -> Result<(i32, i32)> {
         let p = self.transform * (x, y);
-        let mut x = p.x.round().to_i32().ok_or(Error::InvalidValue())?;
-        let mut y = p.y.round().to_i32().ok_or(Error::InvalidValue())?;
-        x = x.clamp(self.x_min, self.x_max);
-        y = y.clamp(self.y_min, self.y_max);
+        let (x, y) = if self.grid > 1 {
+            let g = F::from(self.grid).ok_or(Error::InvalidValue())?;
+            (self.snap.snap(p.x / g) * g, self.snap.snap(p.y / g) * g)
+        } else {
+            (self.snap.snap(p.x), self.snap.snap(p.y))
+        };
+        let x = x.to_i32().ok_or(Error::InvalidValue())?;
+        let y = y.to_i32().ok_or(Error::InvalidValue())?;
         Ok((x, y))
     }
 
@@ -363,29 +853,158 @@ where
         //        if "MUST" rules in the spec are violated
         match self.geom_tp {
             GeomType::Point => {
-                self.set_command_count(self.count);
+                if self.count > 0 {
+                    self.set_command_count(self.count);
+                }
                 // early return skips geometry reset
                 return Ok(());
             }
             GeomType::Linestring => {
                 if self.count > 1 {
-                    self.set_command_count(self.count - 1);
+                    let length = path_length(&self.part);
+                    if length < self.min_length {
+                        self.drop_part(length);
+                    } else {
+                        self.set_command_count(self.count - 1);
+                    }
                 }
             }
             GeomType::Polygon => {
+                // clip closing segment (ClosePath returns to the first point)
+                if let Some(start) = self.xy_start.take() {
+                    self.measures.restore_start();
+                    #[cfg(feature = "mvt3")]
+                    self.elevations.restore_start();
+                    self.add_boundary_points(start.x, start.y)?;
+                }
                 if self.count > 1 {
-                    self.set_command_count(self.count - 1);
-                    self.push_command(Command::ClosePath);
+                    let area = ring_area(&self.part).abs();
+                    // rings with zero area are invalid
+                    if area < self.min_area || area == 0.0 {
+                        self.drop_part(area);
+                    } else {
+                        if self.version == 1 {
+                            self.close_ring();
+                        }
+                        self.set_command_count(self.count - 1);
+                        self.push_command(Command::ClosePath);
+                    }
                 }
             }
         }
         // reset linestring / polygon geometry state
+        self.part.clear();
         self.count = 0;
         self.xy_end = None;
+        self.xy_start = None;
         self.pt0 = None;
         Ok(())
     }
 
+    /// Close the current polygon ring with an explicit point.
+    fn close_ring(&mut self) {
+        if let Some(&pt) = self.part.first()
+            && self.pt1 != Some(pt)
+        {
+            self.measures.repeat(self.part_vertices);
+            #[cfg(feature = "mvt3")]
+            self.elevations.repeat(self.part_vertices);
+            self.push_point(pt.0, pt.1);
+        }
+    }
+
+    /// Drop the current linestring / polygon ring.
+    ///
+    /// * `size` Length or area of the part.
+    fn drop_part(&mut self, size: f64) {
+        log::trace!("drop_part: {size}");
+        if self.keep_point && self.dropped.is_none_or(|(sz, _)| size > sz) {
+            let x = self.part.iter().map(|p| p.0);
+            let y = self.part.iter().map(|p| p.1);
+            let x = midpoint(x.clone().min(), x.max());
+            let y = midpoint(y.clone().min(), y.max());
+            self.dropped = Some((size, (x, y)));
+        }
+        self.data.truncate(self.part_offset);
+        self.pt1 = self.part_cursor;
+        self.vertices = self.part_vertices;
+        self.measures.truncate(self.part_vertices);
+        #[cfg(feature = "mvt3")]
+        self.elevations.truncate(self.part_vertices);
+    }
+
+    /// Add a complete polygon ring, with winding order corrected.
+    ///
+    /// * `ring` Ring points (closing point optional).
+    /// * `exterior` True for exterior rings, false for interior rings.
+    ///
+    /// Exterior rings must have positive area in tile coördinates, and
+    /// interior rings negative area (except in version 1 layers).
+    #[cfg(any(feature = "geozero", feature = "wkb"))]
+    pub(crate) fn add_ring(
+        &mut self,
+        ring: &[(F, F)],
+        exterior: bool,
+    ) -> Result<()> {
+        let ring = match (ring.first(), ring.last()) {
+            (Some(first), Some(last)) if ring.len() > 1 && first == last => {
+                &ring[..ring.len() - 1]
+            }
+            _ => ring,
+        };
+        let pts: Vec<Pt<F>> = ring
+            .iter()
+            .map(|(x, y)| self.transform * (*x, *y))
+            .collect();
+        let n = pts.len();
+        let mut area = F::zero();
+        for i in 0..n {
+            let (p0, p1) = (pts[i], pts[(i + 1) % n]);
+            area = area + p0.x * p1.y - p1.x * p0.y;
+        }
+        let forward = self.version == 1 || (area > F::zero()) == exterior;
+        if forward {
+            for (x, y) in ring {
+                self.add_point(*x, *y)?;
+            }
+        } else {
+            for (x, y) in ring.iter().rev() {
+                self.add_point(*x, *y)?;
+            }
+        }
+        self.complete_geom()
+    }
+
+    /// Get the geometry type.
+    #[cfg(feature = "geozero")]
+    pub(crate) fn geom_type(&self) -> GeomType {
+        self.geom_tp
+    }
+
+    /// Begin a polygon (for geozero input).
+    #[cfg(feature = "geozero")]
+    pub(crate) fn begin_polygon(&mut self) {
+        self.ring.clear();
+        self.ring_count = 0;
+    }
+
+    /// Add a point to the current polygon ring (for geozero input).
+    #[cfg(feature = "geozero")]
+    pub(crate) fn add_ring_point(&mut self, x: F, y: F) {
+        self.ring.push((x, y));
+    }
+
+    /// Complete the current polygon ring (for geozero input).
+    ///
+    /// The first ring of each polygon is exterior, and any others interior.
+    #[cfg(feature = "geozero")]
+    pub(crate) fn complete_ring(&mut self) -> Result<()> {
+        let ring = std::mem::take(&mut self.ring);
+        let exterior = self.ring_count == 0;
+        self.ring_count += 1;
+        self.add_ring(&ring, exterior)
+    }
+
     /// Complete the current geometry (for multilinestring / multipolygon).
     pub fn complete(mut self) -> Result<Self> {
         self.complete_geom()?;
@@ -393,11 +1012,35 @@ where
     }
 
     /// Encode the geometry data, consuming the encoder.
+    ///
+    /// The geometry type of the result is normally the same as the encoder.
+    /// The only exception is when [keep_point](#method.keep_point) is
+    /// enabled and all linestrings or polygon rings are omitted by
+    /// [min_length](#method.min_length) or [min_area](#method.min_area); the
+    /// result is then `Point` geometry (see
+    /// [GeomData::geom_type](struct.GeomData.html#method.geom_type)).
     pub fn encode(mut self) -> Result<GeomData> {
         // FIXME: return Error::InvalidGeometry
         //        if "MUST" rules in the spec are violated
         self = self.complete()?;
-        Ok(GeomData::new(self.geom_tp, self.data))
+        if let (true, Some((_, (x, y)))) = (self.data.is_empty(), self.dropped)
+        {
+            let data = vec![
+                CommandInt::new(Command::MoveTo, 1).encode(),
+                ParamInt::new(x).encode(),
+                ParamInt::new(y).encode(),
+            ];
+            return Ok(GeomData::new(GeomType::Point, data)
+                .with_stats(self.input_points, self.clipped));
+        }
+        let mut geom_data = GeomData::new(self.geom_tp, self.data)
+            .with_stats(self.input_points, self.clipped);
+        geom_data.measures = self.measures.values;
+        #[cfg(feature = "mvt3")]
+        {
+            geom_data.elevations = self.elevations.values;
+        }
+        Ok(geom_data)
     }
 }
 
@@ -407,14 +1050,44 @@ impl GeomData {
     /// * `geom_tp` Geometry type.
     /// * `data` Validated geometry.
     fn new(geom_tp: GeomType, data: Vec<u32>) -> Self {
-        GeomData { geom_tp, data }
+        GeomData {
+            geom_tp,
+            data,
+            paths: OnceLock::new(),
+            input_points: 0,
+            clipped: false,
+            measures: None,
+            #[cfg(feature = "mvt3")]
+            elevations: None,
+        }
     }
 
-    /// Get the geometry type
-    pub(crate) fn geom_type(&self) -> GeomType {
+    /// Set encoding statistics.
+    ///
+    /// * `input_points` Count of input points.
+    /// * `clipped` Flag indicating geometry was clipped.
+    fn with_stats(mut self, input_points: usize, clipped: bool) -> Self {
+        self.input_points = input_points;
+        self.clipped = clipped;
+        self
+    }
+
+    /// Get the geometry type.
+    pub fn geom_type(&self) -> GeomType {
         self.geom_tp
     }
 
+    /// Get measures of each vertex (if any were added)
+    pub(crate) fn measures(&self) -> Option<&[f64]> {
+        self.measures.as_deref()
+    }
+
+    /// Get elevations of each vertex (if any were added)
+    #[cfg(feature = "mvt3")]
+    pub(crate) fn elevations(&self) -> Option<&[f64]> {
+        self.elevations.as_deref()
+    }
+
     /// Check if data is empty
     pub fn is_empty(&self) -> bool {
         self.data.is_empty()
@@ -429,6 +1102,211 @@ impl GeomData {
     pub(crate) fn into_vec(self) -> Vec<u32> {
         self.data
     }
+
+    /// Decode geometry paths.
+    ///
+    /// Paths are decoded once, on first use.  Data produced by the encoder
+    /// is always valid; if it could not be decoded, the paths would be empty
+    /// (and all metrics zero or `None`).
+    pub(crate) fn paths(&self) -> &[Path] {
+        self.paths.get_or_init(|| {
+            decode_paths(self.geom_tp, &self.data).unwrap_or_default()
+        })
+    }
+
+    /// Get count of points added to the encoder
+    pub fn input_points(&self) -> usize {
+        self.input_points
+    }
+
+    /// Get count of encoded points.
+    ///
+    /// This can differ from input points due to clipping, simplification and
+    /// thresholds.
+    pub fn output_points(&self) -> usize {
+        self.paths().iter().map(|path| path.len()).sum()
+    }
+
+    /// Check if any points were clipped or clamped to the bounding box
+    pub fn is_clipped(&self) -> bool {
+        self.clipped
+    }
+
+    /// Get count of parts: points, linestrings or polygon rings
+    pub fn num_parts(&self) -> usize {
+        let paths = self.paths();
+        match self.geom_tp {
+            GeomType::Point => paths.iter().map(|path| path.len()).sum(),
+            _ => paths.len(),
+        }
+    }
+
+    /// Get the bounding box (tile coördinates), or `None` if empty
+    pub fn bounds(&self) -> Option<TileBounds> {
+        let paths = self.paths();
+        let mut pts = paths.iter().flatten();
+        let (x, y) = *pts.next()?;
+        let mut bounds = TileBounds {
+            x_min: x,
+            y_min: y,
+            x_max: x,
+            y_max: y,
+        };
+        for (x, y) in pts {
+            bounds.x_min = bounds.x_min.min(*x);
+            bounds.y_min = bounds.y_min.min(*y);
+            bounds.x_max = bounds.x_max.max(*x);
+            bounds.y_max = bounds.y_max.max(*y);
+        }
+        Some(bounds)
+    }
+
+    /// Calculate total length (tile units).
+    ///
+    /// For linestrings, this is the sum of all linestring lengths.  For
+    /// polygons, it is the sum of all ring perimeters.  For points, it is
+    /// zero.
+    pub fn length(&self) -> f64 {
+        let paths = self.paths();
+        match self.geom_tp {
+            GeomType::Point => 0.0,
+            GeomType::Linestring => {
+                paths.iter().map(|path| path_length(path)).sum()
+            }
+            GeomType::Polygon => paths
+                .iter()
+                .filter_map(|ring| {
+                    let closing = [*ring.last()?, *ring.first()?];
+                    Some(path_length(ring) + path_length(&closing))
+                })
+                .sum(),
+        }
+    }
+
+    /// Calculate signed area of each polygon ring (square tile units).
+    ///
+    /// Exterior rings have positive area, and interior rings negative.  For
+    /// points and linestrings, this is empty.
+    pub fn ring_areas(&self) -> Vec<f64> {
+        match self.geom_tp {
+            GeomType::Polygon => {
+                self.paths().iter().map(|ring| ring_area(ring)).collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Calculate total area (square tile units).
+    ///
+    /// Interior ring areas are subtracted from exterior ring areas.
+    pub fn area(&self) -> f64 {
+        self.ring_areas().iter().sum()
+    }
+
+    /// Calculate the centroid (tile coördinates), or `None` if empty.
+    ///
+    /// For points, this is the mean position.  For linestrings, segments are
+    /// weighted by length, and for polygons, rings are weighted by signed
+    /// area.  Degenerate geometry falls back to the mean vertex position.
+    pub fn centroid(&self) -> Option<(f64, f64)> {
+        let paths = self.paths();
+        let (mut cx, mut cy, mut total) = (0.0, 0.0, 0.0);
+        match self.geom_tp {
+            GeomType::Point => (),
+            GeomType::Linestring => {
+                for w in paths.iter().flat_map(|path| path.windows(2)) {
+                    let len = path_length(w);
+                    let (x0, y0) = (f64::from(w[0].0), f64::from(w[0].1));
+                    let (x1, y1) = (f64::from(w[1].0), f64::from(w[1].1));
+                    cx += len * (x0 + x1) / 2.0;
+                    cy += len * (y0 + y1) / 2.0;
+                    total += len;
+                }
+            }
+            GeomType::Polygon => {
+                for ring in paths {
+                    let n = ring.len();
+                    for i in 0..n {
+                        let (x0, y0) = ring[i];
+                        let (x1, y1) = ring[(i + 1) % n];
+                        let (x0, y0) = (f64::from(x0), f64::from(y0));
+                        let (x1, y1) = (f64::from(x1), f64::from(y1));
+                        let cross = x0 * y1 - x1 * y0;
+                        cx += (x0 + x1) * cross;
+                        cy += (y0 + y1) * cross;
+                    }
+                    total += ring_area(ring) * 6.0;
+                }
+            }
+        }
+        if total != 0.0 {
+            return Some((cx / total, cy / total));
+        }
+        let n = paths.iter().map(|path| path.len()).sum::<usize>();
+        if n == 0 {
+            return None;
+        }
+        let (sx, sy) =
+            paths.iter().flatten().fold((0.0, 0.0), |(sx, sy), (x, y)| {
+                (sx + f64::from(*x), sy + f64::from(*y))
+            });
+        Some((sx / n as f64, sy / n as f64))
+    }
+}
+
+/// Calculate midpoint of two values, without overflow
+fn midpoint(a: Option<i32>, b: Option<i32>) -> i32 {
+    let (a, b) = (a.unwrap_or(0), b.unwrap_or(0));
+    ((i64::from(a) + i64::from(b)) / 2) as i32
+}
+
+/// Calculate length of a path (tile units).
+pub(crate) fn path_length(path: &[(i32, i32)]) -> f64 {
+    path.windows(2)
+        .map(|w| {
+            let dx = f64::from(w[1].0) - f64::from(w[0].0);
+            let dy = f64::from(w[1].1) - f64::from(w[0].1);
+            dx.hypot(dy)
+        })
+        .sum()
+}
+
+/// Calculate signed area of a polygon ring (square tile units).
+///
+/// Using the surveyor's formula, exterior rings have positive area in tile
+/// coördinates (with Y increasing downward).
+pub(crate) fn ring_area(ring: &[(i32, i32)]) -> f64 {
+    let n = ring.len();
+    let mut area = 0;
+    for i in 0..n {
+        let (x0, y0) = ring[i];
+        let (x1, y1) = ring[(i + 1) % n];
+        area +=
+            i128::from(x0) * i128::from(y1) - i128::from(x1) * i128::from(y0);
+    }
+    area as f64 / 2.0
+}
+
+impl TileBounds {
+    /// Get the minimum X value
+    pub fn x_min(&self) -> i32 {
+        self.x_min
+    }
+
+    /// Get the minimum Y value
+    pub fn y_min(&self) -> i32 {
+        self.y_min
+    }
+
+    /// Get the maximum X value
+    pub fn x_max(&self) -> i32 {
+        self.x_max
+    }
+
+    /// Get the maximum Y value
+    pub fn y_max(&self) -> i32 {
+        self.y_max
+    }
 }
 
 #[cfg(test)]
@@ -543,6 +1421,254 @@ mod test {
         assert_eq!(v, vec!(9, 6, 12, 18, 10, 12, 24, 44, 15));
     }
 
+    #[test]
+    fn test_min_length() {
+        let v = GeomEncoder::new(GeomType::Linestring)
+            .min_length(5.0)
+            .point(0.0, 0.0)
+            .unwrap()
+            .point(3.0, 0.0)
+            .unwrap()
+            .complete()
+            .unwrap()
+            .point(10.0, 10.0)
+            .unwrap()
+            .point(10.0, 20.0)
+            .unwrap()
+            .encode()
+            .unwrap()
+            .into_vec();
+        assert_eq!(v, vec!(9, 20, 20, 10, 0, 20));
+    }
+
+    #[test]
+    fn test_min_area() {
+        let v = GeomEncoder::new(GeomType::Polygon)
+            .min_area(10.0)
+            .point(0.0, 0.0)
+            .unwrap()
+            .point(2.0, 0.0)
+            .unwrap()
+            .point(2.0, 2.0)
+            .unwrap()
+            .encode()
+            .unwrap();
+        assert!(v.is_empty());
+        let v = GeomEncoder::new(GeomType::Polygon)
+            .min_area(10.0)
+            .keep_point(true)
+            .point(0.0, 0.0)
+            .unwrap()
+            .point(4.0, 0.0)
+            .unwrap()
+            .point(4.0, 4.0)
+            .unwrap()
+            .encode()
+            .unwrap();
+        assert_eq!(v.geom_type(), GeomType::Point);
+        assert_eq!(v.into_vec(), vec!(9, 4, 4));
+    }
+
+    #[test]
+    fn test_point_part() {
+        let mut enc = GeomEncoder::new(GeomType::Point);
+        for i in 0..10 {
+            enc.add_point(f64::from(i), 0.0).unwrap();
+        }
+        assert!(enc.part.is_empty());
+        assert_eq!(enc.encode().unwrap().len(), 21);
+    }
+
+    #[test]
+    fn test_snap() {
+        let v = GeomEncoder::new(GeomType::Point)
+            .snap(Snap::Floor)
+            .point(2.7, 3.9)
+            .unwrap()
+            .encode()
+            .unwrap()
+            .into_vec();
+        assert_eq!(v, vec!(9, 4, 6));
+        let v = GeomEncoder::new(GeomType::Point)
+            .snap(Snap::Ceil)
+            .point(2.1, 3.1)
+            .unwrap()
+            .encode()
+            .unwrap()
+            .into_vec();
+        assert_eq!(v, vec!(9, 6, 8));
+        let v = GeomEncoder::new(GeomType::Point)
+            .snap(Snap::Topology)
+            .point(-2.5, 2.5)
+            .unwrap()
+            .encode()
+            .unwrap()
+            .into_vec();
+        assert_eq!(v, vec!(9, 3, 6));
+    }
+
+    #[test]
+    fn test_grid() {
+        let v = GeomEncoder::new(GeomType::Point)
+            .grid(4)
+            .point(5.0, 7.0)
+            .unwrap()
+            .encode()
+            .unwrap()
+            .into_vec();
+        assert_eq!(v, vec!(9, 8, 16));
+    }
+
+    #[test]
+    fn test_topology() {
+        let v = GeomEncoder::new(GeomType::Polygon)
+            .snap(Snap::Topology)
+            .point(0.0, 0.0)
+            .unwrap()
+            .point(5.0, 0.0)
+            .unwrap()
+            .point(10.0, 0.0)
+            .unwrap()
+            .point(10.0, 10.0)
+            .unwrap()
+            .encode()
+            .unwrap()
+            .into_vec();
+        assert_eq!(v, vec!(9, 0, 0, 26, 10, 0, 10, 0, 0, 20, 15));
+    }
+
+    #[test]
+    fn test_point_clipping() {
+        let bbox = BBox::new([(0.0, 0.0), (100.0, 100.0)]);
+        let v = GeomEncoder::new(GeomType::Point)
+            .bbox(bbox)
+            .buffer(10)
+            .point(50.0, 50.0)
+            .unwrap()
+            .point(150.0, 50.0)
+            .unwrap()
+            .point(105.0, -5.0)
+            .unwrap()
+            .encode()
+            .unwrap()
+            .into_vec();
+        assert_eq!(v, vec!(17, 100, 100, 110, 109));
+        let v = GeomEncoder::new(GeomType::Point)
+            .bbox(bbox)
+            .point(150.0, 50.0)
+            .unwrap()
+            .encode()
+            .unwrap();
+        assert!(v.is_empty());
+    }
+
+    #[test]
+    fn test_stats() {
+        let bbox = BBox::new([(0.0, 0.0), (100.0, 100.0)]);
+        let v = GeomEncoder::new(GeomType::Linestring)
+            .bbox(bbox)
+            .point(50.0, 50.0)
+            .unwrap()
+            .point(150.0, 50.0)
+            .unwrap()
+            .complete()
+            .unwrap()
+            .point(10.0, 10.0)
+            .unwrap()
+            .point(20.0, 20.0)
+            .unwrap()
+            .encode()
+            .unwrap();
+        assert_eq!(v.input_points(), 4);
+        assert_eq!(v.output_points(), 4);
+        assert!(v.is_clipped());
+        assert_eq!(v.num_parts(), 2);
+        let bounds = v.bounds().unwrap();
+        assert_eq!(bounds.x_min(), 10);
+        assert_eq!(bounds.y_min(), 10);
+        assert_eq!(bounds.x_max(), 100);
+        assert_eq!(bounds.y_max(), 50);
+        let v = GeomEncoder::new(GeomType::Point)
+            .bbox(bbox)
+            .point(10.0, 10.0)
+            .unwrap()
+            .point(20.0, 20.0)
+            .unwrap()
+            .encode()
+            .unwrap();
+        assert!(!v.is_clipped());
+        assert_eq!(v.num_parts(), 2);
+    }
+
+    #[test]
+    fn test_metrics_large() {
+        let v = GeomEncoder::new(GeomType::Linestring)
+            .point(2.0e9, 2.0e9)
+            .unwrap()
+            .point(2.1e9, 2.1e9)
+            .unwrap()
+            .encode()
+            .unwrap();
+        let (x, y) = v.centroid().unwrap();
+        assert!((x - 2.05e9).abs() < 1.0);
+        assert!((y - 2.05e9).abs() < 1.0);
+        // deltas must fit in i32, so keep collinear midpoints
+        let mut enc = GeomEncoder::new(GeomType::Polygon).snap(Snap::Topology);
+        for (x, y) in
+            [(-2, -2), (0, -2), (2, -2), (2, 0), (2, 2), (0, 2), (-2, 2)]
+        {
+            enc.add_point(f64::from(x) * 1.0e9, f64::from(y) * 1.0e9)
+                .unwrap();
+        }
+        let v = enc.encode().unwrap();
+        assert_eq!(v.area(), 1.6e19);
+        assert_eq!(v.centroid(), Some((0.0, 0.0)));
+    }
+
+    #[test]
+    fn test_metrics() {
+        let v = GeomEncoder::new(GeomType::Polygon)
+            .point(0.0, 0.0)
+            .unwrap()
+            .point(10.0, 0.0)
+            .unwrap()
+            .point(10.0, 10.0)
+            .unwrap()
+            .point(0.0, 10.0)
+            .unwrap()
+            .complete()
+            .unwrap()
+            .point(2.0, 2.0)
+            .unwrap()
+            .point(2.0, 4.0)
+            .unwrap()
+            .point(4.0, 4.0)
+            .unwrap()
+            .point(4.0, 2.0)
+            .unwrap()
+            .encode()
+            .unwrap();
+        assert_eq!(v.ring_areas(), vec![100.0, -4.0]);
+        assert_eq!(v.area(), 96.0);
+        assert_eq!(v.length(), 48.0);
+        let (x, y) = v.centroid().unwrap();
+        assert!((x - 5.083).abs() < 0.001);
+        assert!((y - 5.083).abs() < 0.001);
+        let v = GeomEncoder::new(GeomType::Linestring)
+            .point(0.0, 0.0)
+            .unwrap()
+            .point(10.0, 0.0)
+            .unwrap()
+            .point(10.0, 30.0)
+            .unwrap()
+            .encode()
+            .unwrap();
+        assert_eq!(v.length(), 40.0);
+        assert_eq!(v.centroid(), Some((8.75, 11.25)));
+        let v = GeomEncoder::<f64>::new(GeomType::Point).encode().unwrap();
+        assert_eq!(v.centroid(), None);
+    }
+
     #[test]
     fn test_multipolygon() {
         let v = GeomEncoder::new(GeomType::Polygon)
@@ -588,4 +1714,26 @@ mod test {
             )
         );
     }
+
+    #[test]
+    fn test_version() {
+        assert!(GeomEncoder::<f64>::new(GeomType::Point).version(0).is_err());
+        assert!(GeomEncoder::<f64>::new(GeomType::Point).version(3).is_err());
+        let geom_data = GeomEncoder::new(GeomType::Polygon)
+            .version(1)
+            .unwrap()
+            .point_m(0.0, 0.0, 1.0)
+            .unwrap()
+            .point_m(10.0, 0.0, 2.0)
+            .unwrap()
+            .point_m(10.0, 10.0, 3.0)
+            .unwrap()
+            .encode()
+            .unwrap();
+        assert_eq!(geom_data.measures(), Some(&[1.0, 2.0, 3.0, 1.0][..]));
+        assert_eq!(
+            geom_data.into_vec(),
+            vec!(9, 0, 0, 26, 20, 0, 0, 20, 19, 19, 15)
+        );
+    }
 }
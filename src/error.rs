@@ -11,6 +11,10 @@ pub enum Error {
     #[error("Duplicate name")]
     DuplicateName(),
 
+    /// The layer already contains a feature with the specified ID.
+    #[error("Duplicate feature ID")]
+    DuplicateId(),
+
     /// The layer extent does not match the tile extent.
     #[error("Wrong layer extent")]
     WrongExtent(),
@@ -23,10 +27,49 @@ pub enum Error {
     #[error("Invalid float value")]
     InvalidValue(),
 
+    /// Invalid WKB data
+    #[error("Invalid WKB data")]
+    InvalidWkb(),
+
+    /// Invalid WKT text
+    #[error("Invalid WKT text")]
+    InvalidWkt(),
+
+    /// Invalid child quadrant path
+    #[error("Invalid quadrant")]
+    InvalidQuadrant(),
+
+    /// Invalid extension field
+    #[error("Invalid extension field")]
+    InvalidExtension(),
+
+    /// The layer version does not support an operation
+    #[error("Wrong layer version")]
+    WrongVersion(),
+
+    /// Feature extension fields (such as measures) cannot be preserved by an
+    /// operation
+    #[error("Feature extension fields cannot be preserved")]
+    FeatureExtension(),
+
+    /// Error while encoding PNG image
+    #[cfg(feature = "raster")]
+    #[error("PNG error {0}")]
+    Png(#[from] png::EncodingError),
+
+    /// Serde serialization / deserialization error
+    #[cfg(feature = "serde")]
+    #[error("Serde error: {0}")]
+    Serde(String),
+
     /// Error while encoding protobuf data.
     #[error("Protobuf error {0}")]
     Protobuf(#[from] prost::EncodeError),
 
+    /// Error while decoding protobuf data.
+    #[error("Protobuf decode error {0}")]
+    Decode(#[from] prost::DecodeError),
+
     #[error("{0}")]
     Io(#[from] std::io::Error),
 }
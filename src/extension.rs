@@ -0,0 +1,457 @@
+// extension.rs
+//
+// Copyright (c) 2019-2026  Minnesota Department of Transportation
+//
+//! Preservation of unknown (extension) protobuf fields.
+//!
+//! Prost discards unknown fields when decoding, so they are scanned from the
+//! raw data separately, and stored as encoded bytes.
+use crate::error::{Error, Result};
+use crate::vector_tile::Tile as VecTile;
+use crate::vector_tile::tile::Layer as VtLayer;
+use prost::Message;
+use std::collections::HashMap;
+
+/// Wire type of varint fields
+const WIRE_VARINT: u64 = 0;
+
+/// Wire type of 64-bit fields
+pub(crate) const WIRE_I64: u64 = 1;
+
+/// Wire type of length-delimited fields
+pub(crate) const WIRE_LEN: u64 = 2;
+
+/// Wire type of group start (deprecated)
+const WIRE_SGROUP: u64 = 3;
+
+/// Wire type of group end (deprecated)
+const WIRE_EGROUP: u64 = 4;
+
+/// Wire type of 32-bit fields
+const WIRE_I32: u64 = 5;
+
+/// Tile field number of layers
+const TILE_LAYERS: u32 = 3;
+
+/// Known layer field numbers
+const LAYER_FIELDS: [u32; 6] = [1, 2, 3, 4, 5, 15];
+
+/// Layer field number of features
+const LAYER_FEATURES: u32 = 2;
+
+/// Layer field number of values
+const LAYER_VALUES: u32 = 4;
+
+/// Known feature field numbers
+const FEATURE_FIELDS: [u32; 4] = [1, 2, 3, 4];
+
+/// Known value field numbers
+const VALUE_FIELDS: [u32; 7] = [1, 2, 3, 4, 5, 6, 7];
+
+/// Unknown fields of a layer, including its features and values
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct LayerExt {
+    /// Encoded layer fields
+    pub(crate) fields: Vec<u8>,
+
+    /// Encoded fields of features, by index
+    pub(crate) features: HashMap<usize, Vec<u8>>,
+
+    /// Encoded fields of values, by index
+    pub(crate) values: HashMap<usize, Vec<u8>>,
+}
+
+impl LayerExt {
+    /// Check if there are no unknown fields
+    pub(crate) fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+            && self.features.is_empty()
+            && self.values.is_empty()
+    }
+}
+
+/// Protobuf field
+struct Field<'a> {
+    /// Field number
+    number: u32,
+
+    /// Wire type
+    wire_tp: u64,
+
+    /// Raw encoded field (including key)
+    raw: &'a [u8],
+
+    /// Payload (value bytes of fixed-width and length-delimited fields)
+    payload: &'a [u8],
+}
+
+/// Reader for protobuf fields
+struct FieldReader<'a> {
+    /// Encoded message
+    data: &'a [u8],
+
+    /// Offset of next byte
+    offset: usize,
+}
+
+impl<'a> FieldReader<'a> {
+    /// Create a new field reader
+    fn new(data: &'a [u8]) -> Self {
+        FieldReader { data, offset: 0 }
+    }
+
+    /// Read a varint
+    fn read_varint(&mut self) -> Result<u64> {
+        let mut val = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = *self
+                .data
+                .get(self.offset)
+                .ok_or(Error::InvalidExtension())?;
+            self.offset += 1;
+            val |= u64::from(byte & 0x7F) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(val);
+            }
+        }
+        Err(Error::InvalidExtension())
+    }
+
+    /// Advance offset by a number of bytes
+    fn advance(&mut self, len: u64) -> Result<usize> {
+        let start = self.offset;
+        let len =
+            usize::try_from(len).map_err(|_| Error::InvalidExtension())?;
+        let end = start.checked_add(len).ok_or(Error::InvalidExtension())?;
+        if end > self.data.len() {
+            return Err(Error::InvalidExtension());
+        }
+        self.offset = end;
+        Ok(start)
+    }
+
+    /// Read the next field.
+    ///
+    /// Groups are read up to the matching end-group tag, with no payload.
+    fn next_field(&mut self) -> Result<Option<Field<'a>>> {
+        let field = self.next_field_or_end()?;
+        if field.as_ref().is_some_and(|f| f.wire_tp == WIRE_EGROUP) {
+            return Err(Error::InvalidExtension());
+        }
+        Ok(field)
+    }
+
+    /// Skip fields of a group, up to its end-group tag
+    fn skip_group(&mut self, number: u32) -> Result<()> {
+        while let Some(field) = self.next_field_or_end()? {
+            if field.wire_tp == WIRE_EGROUP {
+                if field.number == number {
+                    return Ok(());
+                }
+                break;
+            }
+        }
+        Err(Error::InvalidExtension())
+    }
+
+    /// Read the next field, or an end-group tag
+    fn next_field_or_end(&mut self) -> Result<Option<Field<'a>>> {
+        if self.offset >= self.data.len() {
+            return Ok(None);
+        }
+        let start = self.offset;
+        let key = self.read_varint()?;
+        let number =
+            u32::try_from(key >> 3).map_err(|_| Error::InvalidExtension())?;
+        let wire_tp = key & 0x7;
+        let mut payload: &[u8] = &[];
+        match wire_tp {
+            WIRE_VARINT => {
+                self.read_varint()?;
+            }
+            WIRE_I64 => {
+                let pos = self.advance(8)?;
+                payload = &self.data[pos..self.offset];
+            }
+            WIRE_LEN => {
+                let len = self.read_varint()?;
+                let pos = self.advance(len)?;
+                payload = &self.data[pos..self.offset];
+            }
+            WIRE_SGROUP => self.skip_group(number)?,
+            WIRE_EGROUP => (),
+            WIRE_I32 => {
+                let pos = self.advance(4)?;
+                payload = &self.data[pos..self.offset];
+            }
+            _ => return Err(Error::InvalidExtension()),
+        }
+        Ok(Some(Field {
+            number,
+            wire_tp,
+            raw: &self.data[start..self.offset],
+            payload,
+        }))
+    }
+}
+
+/// Get unknown fields of a message.
+///
+/// * `data` Encoded message.
+/// * `known` Known field numbers.
+fn unknown_fields(data: &[u8], known: &[u32]) -> Result<Vec<u8>> {
+    let mut fields = Vec::new();
+    let mut reader = FieldReader::new(data);
+    while let Some(field) = reader.next_field()? {
+        if !known.contains(&field.number) {
+            fields.extend_from_slice(field.raw);
+        }
+    }
+    Ok(fields)
+}
+
+/// Find the payload of a field.
+///
+/// * `data` Encoded fields.
+/// * `number` Field number.
+/// * `wire_tp` Wire type (fixed-width or length-delimited).
+///
+/// Returns the last matching field (as protobuf parsers do).
+pub(crate) fn find_field(
+    data: &[u8],
+    number: u32,
+    wire_tp: u64,
+) -> Option<&[u8]> {
+    let mut found = None;
+    let mut reader = FieldReader::new(data);
+    while let Some(field) = reader.next_field().ok()? {
+        if field.number == number && field.wire_tp == wire_tp {
+            found = Some(field.payload);
+        }
+    }
+    found
+}
+
+/// Read packed varints
+pub(crate) fn read_packed(data: &[u8]) -> Option<Vec<u64>> {
+    let mut vals = Vec::new();
+    let mut reader = FieldReader::new(data);
+    while reader.offset < data.len() {
+        vals.push(reader.read_varint().ok()?);
+    }
+    Some(vals)
+}
+
+/// Scan unknown fields of a layer
+fn scan_layer(data: &[u8]) -> Result<LayerExt> {
+    let mut ext = LayerExt::default();
+    let (mut n_features, mut n_values) = (0, 0);
+    let mut reader = FieldReader::new(data);
+    while let Some(field) = reader.next_field()? {
+        match (field.number, field.wire_tp) {
+            (LAYER_FEATURES, WIRE_LEN) => {
+                let fields = unknown_fields(field.payload, &FEATURE_FIELDS)?;
+                if !fields.is_empty() {
+                    ext.features.insert(n_features, fields);
+                }
+                n_features += 1;
+            }
+            (LAYER_VALUES, WIRE_LEN) => {
+                let fields = unknown_fields(field.payload, &VALUE_FIELDS)?;
+                if !fields.is_empty() {
+                    ext.values.insert(n_values, fields);
+                }
+                n_values += 1;
+            }
+            (number, _) if !LAYER_FIELDS.contains(&number) => {
+                ext.fields.extend_from_slice(field.raw);
+            }
+            _ => (),
+        }
+    }
+    Ok(ext)
+}
+
+/// Scan unknown fields of a tile.
+///
+/// * `data` Encoded tile.
+///
+/// Returns unknown tile fields, and unknown fields of each layer (in
+/// order).
+pub(crate) fn scan_tile(data: &[u8]) -> Result<(Vec<u8>, Vec<LayerExt>)> {
+    let mut fields = Vec::new();
+    let mut layers = Vec::new();
+    let mut reader = FieldReader::new(data);
+    while let Some(field) = reader.next_field()? {
+        if field.number == TILE_LAYERS && field.wire_tp == WIRE_LEN {
+            layers.push(scan_layer(field.payload)?);
+        } else {
+            fields.extend_from_slice(field.raw);
+        }
+    }
+    Ok((fields, layers))
+}
+
+/// Write a varint
+pub(crate) fn write_varint(buf: &mut Vec<u8>, mut val: u64) {
+    while val >= 0x80 {
+        buf.push((val as u8) | 0x80);
+        val >>= 7;
+    }
+    buf.push(val as u8);
+}
+
+/// Encode a value with zigzag encoding
+pub(crate) fn zigzag(val: i64) -> u64 {
+    ((val << 1) ^ (val >> 63)) as u64
+}
+
+/// Decode a zigzag encoded value
+pub(crate) fn unzigzag(val: u64) -> i64 {
+    ((val >> 1) as i64) ^ -((val & 1) as i64)
+}
+
+/// Write a length-delimited field
+pub(crate) fn write_field(buf: &mut Vec<u8>, number: u32, payload: &[u8]) {
+    write_varint(buf, (u64::from(number) << 3) | WIRE_LEN);
+    write_varint(buf, payload.len() as u64);
+    buf.extend_from_slice(payload);
+}
+
+/// Encode a message with unknown fields appended
+fn encode_with<M: Message>(msg: &M, fields: Option<&Vec<u8>>) -> Vec<u8> {
+    let mut buf = msg.encode_to_vec();
+    if let Some(fields) = fields {
+        buf.extend_from_slice(fields);
+    }
+    buf
+}
+
+/// Encode a layer with unknown fields
+fn encode_layer(layer: &VtLayer, ext: &LayerExt) -> Vec<u8> {
+    let base = VtLayer {
+        version: layer.version,
+        name: layer.name.clone(),
+        features: Vec::new(),
+        keys: layer.keys.clone(),
+        values: Vec::new(),
+        extent: layer.extent,
+    };
+    let mut buf = base.encode_to_vec();
+    for (i, feature) in layer.features.iter().enumerate() {
+        let payload = encode_with(feature, ext.features.get(&i));
+        write_field(&mut buf, LAYER_FEATURES, &payload);
+    }
+    for (i, value) in layer.values.iter().enumerate() {
+        let payload = encode_with(value, ext.values.get(&i));
+        write_field(&mut buf, LAYER_VALUES, &payload);
+    }
+    buf.extend_from_slice(&ext.fields);
+    buf
+}
+
+/// Encode a tile with unknown fields.
+///
+/// * `tile` Tile to encode.
+/// * `fields` Unknown tile fields.
+/// * `layers` Unknown layer fields, by layer name.
+pub(crate) fn encode_tile(
+    tile: &VecTile,
+    fields: &[u8],
+    layers: &HashMap<String, LayerExt>,
+) -> Vec<u8> {
+    let mut buf = Vec::new();
+    for layer in &tile.layers {
+        match layers.get(&layer.name) {
+            Some(ext) => {
+                write_field(&mut buf, TILE_LAYERS, &encode_layer(layer, ext))
+            }
+            None => write_field(&mut buf, TILE_LAYERS, &layer.encode_to_vec()),
+        }
+    }
+    buf.extend_from_slice(fields);
+    buf
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::vector_tile::tile::{Feature as VtFeature, Value};
+
+    #[test]
+    fn test_varint() {
+        let mut buf = Vec::new();
+        write_varint(&mut buf, 300);
+        assert_eq!(buf, [0xAC, 0x02]);
+        assert_eq!(FieldReader::new(&buf).read_varint().unwrap(), 300);
+        assert!(FieldReader::new(&[0x80]).read_varint().is_err());
+        assert_eq!(read_packed(&[0xAC, 0x02, 1]).unwrap(), [300, 1]);
+        for val in [0, -1, 1, i64::MIN, i64::MAX] {
+            assert_eq!(unzigzag(zigzag(val)), val);
+        }
+    }
+
+    #[test]
+    fn test_find_field() {
+        // field 1 varint, field 8 string, field 9 fixed32, field 8 string
+        let data = [8, 1, 66, 1, b'a', 77, 1, 2, 3, 4, 66, 1, b'b'];
+        assert_eq!(find_field(&data, 8, WIRE_LEN).unwrap(), b"b");
+        assert_eq!(find_field(&data, 9, WIRE_I32).unwrap(), [1, 2, 3, 4]);
+        assert!(find_field(&data, 9, WIRE_LEN).is_none());
+    }
+
+    #[test]
+    fn test_unknown_fields() {
+        // field 1 varint, field 8 string, field 9 fixed32
+        let data = [8, 1, 66, 2, b'h', b'i', 77, 0, 0, 0, 0];
+        let fields = unknown_fields(&data, &VALUE_FIELDS).unwrap();
+        assert_eq!(fields, [66, 2, b'h', b'i', 77, 0, 0, 0, 0]);
+        assert!(unknown_fields(&[66, 5, 0], &VALUE_FIELDS).is_err());
+    }
+
+    #[test]
+    fn test_groups() {
+        // field 1 varint, group 8 (containing group 9 and field 1), field 10
+        let data = [8, 1, 67, 75, 76, 8, 2, 68, 80, 3];
+        let fields = unknown_fields(&data, &VALUE_FIELDS).unwrap();
+        assert_eq!(fields, [67, 75, 76, 8, 2, 68, 80, 3]);
+        assert_eq!(find_field(&data, 10, WIRE_VARINT), Some(&[][..]));
+        // mismatched / missing end-group tags
+        assert!(unknown_fields(&[67, 76], &VALUE_FIELDS).is_err());
+        assert!(unknown_fields(&[67, 8, 1], &VALUE_FIELDS).is_err());
+        assert!(unknown_fields(&[68], &VALUE_FIELDS).is_err());
+        // layer with a feature containing group 16
+        let tile = [26, 8, 18, 6, 131, 1, 8, 1, 132, 1];
+        assert!(VecTile::decode(&tile[..]).is_ok());
+        let (_, layers) = scan_tile(&tile).unwrap();
+        assert_eq!(layers[0].features[&0], [131, 1, 8, 1, 132, 1]);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let layer = VtLayer {
+            version: 2,
+            name: "test".to_string(),
+            features: vec![VtFeature::default()],
+            keys: vec!["key".to_string()],
+            values: vec![Value {
+                bool_value: Some(true),
+                ..Default::default()
+            }],
+            extent: Some(4096),
+        };
+        let tile = VecTile {
+            layers: vec![layer],
+        };
+        let mut ext = LayerExt::default();
+        write_field(&mut ext.fields, 16, b"layer");
+        ext.features.insert(0, vec![40, 1]);
+        write_field(ext.values.entry(0).or_default(), 8, b"value");
+        let mut fields = Vec::new();
+        write_field(&mut fields, 16, b"tile");
+        let layers = HashMap::from([("test".to_string(), ext.clone())]);
+        let data = encode_tile(&tile, &fields, &layers);
+        assert_eq!(VecTile::decode(&data[..]).unwrap(), tile);
+        assert_eq!(scan_tile(&data).unwrap(), (fields, vec![ext]));
+    }
+}
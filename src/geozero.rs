@@ -0,0 +1,443 @@
+// geozero.rs
+//
+// Copyright (c) 2019-2026  Minnesota Department of Transportation
+//
+//! Processors for [geozero] input.
+//!
+//! [geozero]: https://docs.rs/geozero
+use crate::encoder::{GeomEncoder, GeomType};
+use crate::error::Error;
+use crate::tile::Layer;
+use geozero::error::{GeozeroError, Result};
+use geozero::{
+    ColumnValue, FeatureProcessor, GeomProcessor, PropertyProcessor,
+};
+use pointy::Float;
+
+/// Convert an encoder error to a geozero error
+fn geom_err(err: Error) -> GeozeroError {
+    GeozeroError::Geometry(err.to_string())
+}
+
+/// Check that geometry type matches an encoder
+fn check_type(expected: GeomType, geom_tp: GeomType) -> Result<()> {
+    if expected == geom_tp {
+        Ok(())
+    } else {
+        Err(GeozeroError::Geometry(format!(
+            "{expected:?} geometry for {geom_tp:?} encoder"
+        )))
+    }
+}
+
+/// Geometry processor for an encoder.
+///
+/// The geometry type of the input must match the encoder.  Polygon rings are
+/// buffered, so that winding order can be corrected and closing points
+/// removed.
+impl<F> GeomProcessor for GeomEncoder<F>
+where
+    F: Float,
+{
+    fn xy(&mut self, x: f64, y: f64, _idx: usize) -> Result<()> {
+        let x = F::from(x).ok_or_else(|| geom_err(Error::InvalidValue()))?;
+        let y = F::from(y).ok_or_else(|| geom_err(Error::InvalidValue()))?;
+        if self.geom_type() == GeomType::Polygon {
+            self.add_ring_point(x, y);
+            Ok(())
+        } else {
+            self.add_point(x, y).map_err(geom_err)
+        }
+    }
+
+    fn empty_point(&mut self, _idx: usize) -> Result<()> {
+        Ok(())
+    }
+
+    fn point_begin(&mut self, _idx: usize) -> Result<()> {
+        check_type(GeomType::Point, self.geom_type())
+    }
+
+    fn point_end(&mut self, _idx: usize) -> Result<()> {
+        self.complete_geom().map_err(geom_err)
+    }
+
+    fn multipoint_begin(&mut self, _size: usize, _idx: usize) -> Result<()> {
+        check_type(GeomType::Point, self.geom_type())
+    }
+
+    fn multipoint_end(&mut self, _idx: usize) -> Result<()> {
+        self.complete_geom().map_err(geom_err)
+    }
+
+    fn linestring_begin(
+        &mut self,
+        tagged: bool,
+        _size: usize,
+        _idx: usize,
+    ) -> Result<()> {
+        // untagged linestrings may be polygon rings
+        if tagged || self.geom_type() == GeomType::Point {
+            check_type(GeomType::Linestring, self.geom_type())
+        } else {
+            Ok(())
+        }
+    }
+
+    fn linestring_end(&mut self, _tagged: bool, _idx: usize) -> Result<()> {
+        if self.geom_type() == GeomType::Polygon {
+            self.complete_ring().map_err(geom_err)
+        } else {
+            self.complete_geom().map_err(geom_err)
+        }
+    }
+
+    fn multilinestring_begin(
+        &mut self,
+        _size: usize,
+        _idx: usize,
+    ) -> Result<()> {
+        check_type(GeomType::Linestring, self.geom_type())
+    }
+
+    fn polygon_begin(
+        &mut self,
+        _tagged: bool,
+        _size: usize,
+        _idx: usize,
+    ) -> Result<()> {
+        check_type(GeomType::Polygon, self.geom_type())?;
+        self.begin_polygon();
+        Ok(())
+    }
+
+    fn multipolygon_begin(&mut self, _size: usize, _idx: usize) -> Result<()> {
+        check_type(GeomType::Polygon, self.geom_type())
+    }
+}
+
+/// Tag value of a feature property
+enum TagValue {
+    String(String),
+    Float(f32),
+    Double(f64),
+    Int(i64),
+    Uint(u64),
+    Bool(bool),
+}
+
+impl TagValue {
+    /// Make a tag value from a geozero column value
+    fn from_column(value: &ColumnValue) -> Option<Self> {
+        Some(match value {
+            ColumnValue::Byte(v) => TagValue::Int(i64::from(*v)),
+            ColumnValue::UByte(v) => TagValue::Uint(u64::from(*v)),
+            ColumnValue::Bool(v) => TagValue::Bool(*v),
+            ColumnValue::Short(v) => TagValue::Int(i64::from(*v)),
+            ColumnValue::UShort(v) => TagValue::Uint(u64::from(*v)),
+            ColumnValue::Int(v) => TagValue::Int(i64::from(*v)),
+            ColumnValue::UInt(v) => TagValue::Uint(u64::from(*v)),
+            ColumnValue::Long(v) => TagValue::Int(*v),
+            ColumnValue::ULong(v) => TagValue::Uint(*v),
+            ColumnValue::Float(v) => TagValue::Float(*v),
+            ColumnValue::Double(v) => TagValue::Double(*v),
+            ColumnValue::String(v)
+            | ColumnValue::Json(v)
+            | ColumnValue::DateTime(v) => TagValue::String(v.to_string()),
+            ColumnValue::Binary(_) => return None,
+        })
+    }
+}
+
+/// Feature processor which adds features to a [Layer].
+///
+/// Each geozero feature is encoded with a template [GeomEncoder], whose
+/// transform, bounding box and other settings are applied.  Geometry
+/// collections are split into one feature for each geometry type present
+/// (points, linestrings, then polygons), all with the same tags.
+///
+/// Properties are added as tags:
+/// - Signed integers: [add_tag_int](struct.Feature.html#method.add_tag_int)
+/// - Unsigned integers:
+///   [add_tag_uint](struct.Feature.html#method.add_tag_uint)
+/// - Floats: [add_tag_float](struct.Feature.html#method.add_tag_float) or
+///   [add_tag_double](struct.Feature.html#method.add_tag_double)
+/// - Booleans: [add_tag_bool](struct.Feature.html#method.add_tag_bool)
+/// - Strings, JSON and date-times:
+///   [add_tag_string](struct.Feature.html#method.add_tag_string)
+///
+/// Binary properties are skipped.
+///
+/// # Example
+/// ```
+/// # use mvt::{Error, GeomEncoder, GeomType, LayerProcessor, Tile};
+/// # use geozero::{FeatureProcessor, GeomProcessor, PropertyProcessor};
+/// # use geozero::ColumnValue;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut tile = Tile::new(4096);
+/// let layer = tile.create_layer("First Layer");
+/// let mut proc =
+///     LayerProcessor::new(layer, GeomEncoder::new(GeomType::Point));
+/// proc.feature_begin(0)?;
+/// proc.property(0, "name", &ColumnValue::String("Here"))?;
+/// proc.point_begin(0)?;
+/// proc.xy(10.0, 20.0, 0)?;
+/// proc.point_end(0)?;
+/// proc.feature_end(0)?;
+/// tile.add_layer(proc.into_layer())?;
+/// # Ok(()) }
+/// ```
+pub struct LayerProcessor {
+    /// Layer for adding features
+    layer: Option<Layer>,
+
+    /// Template encoder
+    template: GeomEncoder<f64>,
+
+    /// Encoders for current feature (points, linestrings, polygons)
+    encoders: Vec<GeomEncoder<f64>>,
+
+    /// Current geometry type
+    geom_tp: GeomType,
+
+    /// Tags for current feature
+    tags: Vec<(String, TagValue)>,
+}
+
+impl LayerProcessor {
+    /// Create a new layer processor.
+    ///
+    /// * `layer` Layer for adding features.
+    /// * `template` Encoder with settings for all geometry (the geometry
+    ///   type is ignored).
+    pub fn new(layer: Layer, template: GeomEncoder<f64>) -> Self {
+        let mut proc = LayerProcessor {
+            layer: Some(layer),
+            template,
+            encoders: Vec::new(),
+            geom_tp: GeomType::Point,
+            tags: Vec::new(),
+        };
+        proc.reset();
+        proc
+    }
+
+    /// Get the layer, consuming the processor.
+    pub fn into_layer(self) -> Layer {
+        self.layer.unwrap_or_default()
+    }
+
+    /// Reset feature state
+    fn reset(&mut self) {
+        self.encoders =
+            [GeomType::Point, GeomType::Linestring, GeomType::Polygon]
+                .into_iter()
+                .map(|geom_tp| self.template.with_geom_type(geom_tp))
+                .collect();
+        self.tags.clear();
+    }
+
+    /// Get the encoder for the current geometry type
+    fn encoder(&mut self) -> &mut GeomEncoder<f64> {
+        let i = match self.geom_tp {
+            GeomType::Point => 0,
+            GeomType::Linestring => 1,
+            GeomType::Polygon => 2,
+        };
+        &mut self.encoders[i]
+    }
+
+    /// Add features to the layer
+    fn add_features(&mut self) -> crate::error::Result<()> {
+        // encode all geometry before taking the layer, so it is not lost on
+        // error
+        let geoms = std::mem::take(&mut self.encoders)
+            .into_iter()
+            .map(|enc| enc.encode())
+            .collect::<crate::error::Result<Vec<_>>>()?;
+        let mut layer = self.layer.take().unwrap_or_default();
+        for geom_data in geoms {
+            if geom_data.is_empty() {
+                continue;
+            }
+            let mut feature = layer.into_feature(geom_data);
+            for (key, val) in &self.tags {
+                match val {
+                    TagValue::String(v) => feature.add_tag_string(key, v),
+                    TagValue::Float(v) => feature.add_tag_float(key, *v),
+                    TagValue::Double(v) => feature.add_tag_double(key, *v),
+                    TagValue::Int(v) => feature.add_tag_int(key, *v),
+                    TagValue::Uint(v) => feature.add_tag_uint(key, *v),
+                    TagValue::Bool(v) => feature.add_tag_bool(key, *v),
+                }
+            }
+            layer = feature.into_layer();
+        }
+        self.layer = Some(layer);
+        Ok(())
+    }
+}
+
+impl GeomProcessor for LayerProcessor {
+    fn xy(&mut self, x: f64, y: f64, idx: usize) -> Result<()> {
+        self.encoder().xy(x, y, idx)
+    }
+
+    fn empty_point(&mut self, _idx: usize) -> Result<()> {
+        Ok(())
+    }
+
+    fn point_begin(&mut self, idx: usize) -> Result<()> {
+        self.geom_tp = GeomType::Point;
+        self.encoder().point_begin(idx)
+    }
+
+    fn point_end(&mut self, idx: usize) -> Result<()> {
+        self.encoder().point_end(idx)
+    }
+
+    fn multipoint_begin(&mut self, size: usize, idx: usize) -> Result<()> {
+        self.geom_tp = GeomType::Point;
+        self.encoder().multipoint_begin(size, idx)
+    }
+
+    fn multipoint_end(&mut self, idx: usize) -> Result<()> {
+        self.encoder().multipoint_end(idx)
+    }
+
+    fn linestring_begin(
+        &mut self,
+        tagged: bool,
+        size: usize,
+        idx: usize,
+    ) -> Result<()> {
+        if tagged {
+            self.geom_tp = GeomType::Linestring;
+        }
+        self.encoder().linestring_begin(tagged, size, idx)
+    }
+
+    fn linestring_end(&mut self, tagged: bool, idx: usize) -> Result<()> {
+        self.encoder().linestring_end(tagged, idx)
+    }
+
+    fn multilinestring_begin(&mut self, size: usize, idx: usize) -> Result<()> {
+        self.geom_tp = GeomType::Linestring;
+        self.encoder().multilinestring_begin(size, idx)
+    }
+
+    fn polygon_begin(
+        &mut self,
+        tagged: bool,
+        size: usize,
+        idx: usize,
+    ) -> Result<()> {
+        self.geom_tp = GeomType::Polygon;
+        self.encoder().polygon_begin(tagged, size, idx)
+    }
+
+    fn multipolygon_begin(&mut self, size: usize, idx: usize) -> Result<()> {
+        self.geom_tp = GeomType::Polygon;
+        self.encoder().multipolygon_begin(size, idx)
+    }
+}
+
+impl PropertyProcessor for LayerProcessor {
+    fn property(
+        &mut self,
+        _idx: usize,
+        name: &str,
+        value: &ColumnValue,
+    ) -> Result<bool> {
+        if let Some(val) = TagValue::from_column(value) {
+            self.tags.push((name.to_string(), val));
+        }
+        Ok(false)
+    }
+}
+
+impl FeatureProcessor for LayerProcessor {
+    fn feature_begin(&mut self, _idx: u64) -> Result<()> {
+        self.reset();
+        Ok(())
+    }
+
+    fn feature_end(&mut self, _idx: u64) -> Result<()> {
+        let res = self.add_features();
+        // reset even on error, so encoders are ready for more geometry
+        self.reset();
+        res.map_err(|e| GeozeroError::Feature(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tile::Tile;
+    use crate::vector_tile::tile::GeomType as VtGeomType;
+
+    #[test]
+    fn test_encoder() {
+        let mut enc = GeomEncoder::<f64>::new(GeomType::Polygon);
+        enc.polygon_begin(true, 1, 0).unwrap();
+        enc.linestring_begin(false, 4, 0).unwrap();
+        // counter-clockwise ring, with closing point
+        for (x, y) in [(0.0, 0.0), (0.0, 10.0), (10.0, 10.0), (0.0, 0.0)] {
+            enc.xy(x, y, 0).unwrap();
+        }
+        enc.linestring_end(false, 0).unwrap();
+        enc.polygon_end(true, 0).unwrap();
+        let geom_data = enc.encode().unwrap();
+        assert_eq!(geom_data.paths(), vec![vec![(10, 10), (0, 10), (0, 0)]]);
+        let mut enc = GeomEncoder::<f64>::new(GeomType::Point);
+        assert!(enc.linestring_begin(true, 2, 0).is_err());
+    }
+
+    #[test]
+    fn test_layer_processor() {
+        let tile = Tile::new(4096);
+        let layer = tile.create_layer("test");
+        let mut proc =
+            LayerProcessor::new(layer, GeomEncoder::new(GeomType::Point));
+        proc.feature_begin(0).unwrap();
+        proc.properties_begin().unwrap();
+        proc.property(0, "name", &ColumnValue::String("a")).unwrap();
+        proc.property(1, "count", &ColumnValue::Int(-5)).unwrap();
+        proc.property(2, "blob", &ColumnValue::Binary(&[1]))
+            .unwrap();
+        proc.properties_end().unwrap();
+        proc.geometry_begin().unwrap();
+        proc.geometrycollection_begin(2, 0).unwrap();
+        proc.point_begin(0).unwrap();
+        proc.xy(1.0, 2.0, 0).unwrap();
+        proc.point_end(0).unwrap();
+        proc.linestring_begin(true, 2, 1).unwrap();
+        proc.xy(0.0, 0.0, 0).unwrap();
+        proc.xy(5.0, 5.0, 1).unwrap();
+        proc.linestring_end(true, 1).unwrap();
+        proc.geometrycollection_end(0).unwrap();
+        proc.geometry_end().unwrap();
+        proc.feature_end(0).unwrap();
+        let layer = proc.into_layer();
+        assert_eq!(layer.num_features(), 2);
+        let feature = &layer.layer.features[1];
+        assert_eq!(feature.r#type, Some(VtGeomType::Linestring as i32));
+        assert_eq!(feature.tags, vec![0, 0, 1, 1]);
+    }
+
+    #[test]
+    fn test_geometry_after_feature() {
+        let tile = Tile::new(4096);
+        let layer = tile.create_layer("test");
+        let mut proc =
+            LayerProcessor::new(layer, GeomEncoder::new(GeomType::Point));
+        proc.feature_begin(0).unwrap();
+        proc.feature_end(0).unwrap();
+        // bare geometry, without feature_begin
+        proc.point_begin(0).unwrap();
+        proc.xy(1.0, 2.0, 0).unwrap();
+        proc.point_end(0).unwrap();
+        proc.feature_end(1).unwrap();
+        assert_eq!(proc.into_layer().num_features(), 1);
+    }
+}
@@ -0,0 +1,272 @@
+// label.rs
+//
+// Copyright (c) 2019-2026  Minnesota Department of Transportation
+//
+//! Label point generation for polygons.
+//!
+use crate::decoder::Path;
+use crate::encoder::{GeomData, GeomEncoder, GeomType, ring_area};
+use crate::error::{Error, Result};
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// Minimum search precision (tile units).
+///
+/// The label point is snapped to integer tile coördinates, so there is no
+/// benefit to a finer search.
+const MIN_PRECISION: f64 = 0.5;
+
+/// Square cell used for searching a polygon
+struct Cell {
+    /// Center X
+    x: f64,
+
+    /// Center Y
+    y: f64,
+
+    /// Half of cell size
+    h: f64,
+
+    /// Distance from center to polygon edge (negative if outside)
+    d: f64,
+
+    /// Maximum distance to polygon edge within cell
+    max: f64,
+}
+
+impl PartialEq for Cell {
+    fn eq(&self, other: &Self) -> bool {
+        self.max == other.max
+    }
+}
+
+impl Eq for Cell {}
+
+impl PartialOrd for Cell {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Cell {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.max.total_cmp(&other.max)
+    }
+}
+
+impl Cell {
+    /// Create a new cell
+    fn new(x: f64, y: f64, h: f64, rings: &[Path]) -> Self {
+        let d = signed_distance(x, y, rings);
+        let max = d + h * std::f64::consts::SQRT_2;
+        Cell { x, y, h, d, max }
+    }
+}
+
+/// Calculate squared distance from a point to a segment
+fn segment_dist_sq(px: f64, py: f64, a: (i32, i32), b: (i32, i32)) -> f64 {
+    let (mut x, mut y) = (f64::from(a.0), f64::from(a.1));
+    let dx = f64::from(b.0) - x;
+    let dy = f64::from(b.1) - y;
+    if dx != 0.0 || dy != 0.0 {
+        let t = ((px - x) * dx + (py - y) * dy) / (dx * dx + dy * dy);
+        if t > 1.0 {
+            x = f64::from(b.0);
+            y = f64::from(b.1);
+        } else if t > 0.0 {
+            x += dx * t;
+            y += dy * t;
+        }
+    }
+    let dx = px - x;
+    let dy = py - y;
+    dx * dx + dy * dy
+}
+
+/// Calculate signed distance from a point to polygon rings.
+///
+/// The distance is positive inside the polygon, and negative outside.
+fn signed_distance(x: f64, y: f64, rings: &[Path]) -> f64 {
+    let mut inside = false;
+    let mut min_dist_sq = f64::INFINITY;
+    for ring in rings {
+        let n = ring.len();
+        for i in 0..n {
+            let a = ring[i];
+            let b = ring[(i + n - 1) % n];
+            let (ax, ay) = (f64::from(a.0), f64::from(a.1));
+            let (bx, by) = (f64::from(b.0), f64::from(b.1));
+            if (ay > y) != (by > y) && x < (bx - ax) * (y - ay) / (by - ay) + ax
+            {
+                inside = !inside;
+            }
+            min_dist_sq = min_dist_sq.min(segment_dist_sq(x, y, a, b));
+        }
+    }
+    let dist = min_dist_sq.sqrt();
+    if inside { dist } else { -dist }
+}
+
+/// Find the pole of inaccessibility of a polygon.
+///
+/// * `rings` Exterior ring, followed by any interior rings.
+/// * `precision` Precision of result (tile units).
+fn polylabel(rings: &[Path], precision: f64) -> Option<(f64, f64)> {
+    let exterior = rings.first()?;
+    let (x0, y0) = *exterior.first()?;
+    let (mut x_min, mut y_min, mut x_max, mut y_max) = (x0, y0, x0, y0);
+    for (x, y) in exterior {
+        x_min = x_min.min(*x);
+        y_min = y_min.min(*y);
+        x_max = x_max.max(*x);
+        y_max = y_max.max(*y);
+    }
+    let (x_min, y_min) = (f64::from(x_min), f64::from(y_min));
+    let width = f64::from(x_max) - x_min;
+    let height = f64::from(y_max) - y_min;
+    let cell_size = width.min(height);
+    if cell_size == 0.0 {
+        return Some((x_min + width / 2.0, y_min + height / 2.0));
+    }
+    let h = cell_size / 2.0;
+    let mut cells = BinaryHeap::new();
+    let mut x = x_min;
+    while x < x_min + width {
+        let mut y = y_min;
+        while y < y_min + height {
+            cells.push(Cell::new(x + h, y + h, h, rings));
+            y += cell_size;
+        }
+        x += cell_size;
+    }
+    // Start with the centroid, or the bounding box center
+    let mut best = centroid_cell(exterior, rings);
+    let center =
+        Cell::new(x_min + width / 2.0, y_min + height / 2.0, 0.0, rings);
+    if center.d > best.d {
+        best = center;
+    }
+    while let Some(cell) = cells.pop() {
+        if cell.d > best.d {
+            best = Cell::new(cell.x, cell.y, 0.0, rings);
+        }
+        if cell.max - best.d <= precision {
+            continue;
+        }
+        let h = cell.h / 2.0;
+        cells.push(Cell::new(cell.x - h, cell.y - h, h, rings));
+        cells.push(Cell::new(cell.x + h, cell.y - h, h, rings));
+        cells.push(Cell::new(cell.x - h, cell.y + h, h, rings));
+        cells.push(Cell::new(cell.x + h, cell.y + h, h, rings));
+    }
+    Some((best.x, best.y))
+}
+
+/// Make a cell at the centroid of a ring
+fn centroid_cell(ring: &[(i32, i32)], rings: &[Path]) -> Cell {
+    let area = ring_area(ring);
+    let (x0, y0) = ring[0];
+    let (mut cx, mut cy) = (f64::from(x0), f64::from(y0));
+    if area != 0.0 {
+        let (mut sx, mut sy) = (0.0, 0.0);
+        let n = ring.len();
+        for i in 0..n {
+            let (ax, ay) = ring[i];
+            let (bx, by) = ring[(i + 1) % n];
+            let (ax, ay) = (f64::from(ax), f64::from(ay));
+            let (bx, by) = (f64::from(bx), f64::from(by));
+            let cross = ax * by - bx * ay;
+            sx += (ax + bx) * cross;
+            sy += (ay + by) * cross;
+        }
+        cx = sx / (6.0 * area);
+        cy = sy / (6.0 * area);
+    }
+    Cell::new(cx, cy, 0.0, rings)
+}
+
+impl GeomData {
+    /// Calculate a label point for polygon geometry.
+    ///
+    /// * `precision` Precision of the search (tile units).  Values less than
+    ///   0.5 (including NaN) are treated as 0.5.
+    ///
+    /// The label point is the pole of inaccessibility: the point within the
+    /// polygon which is farthest from any edge.  For multipolygons, the
+    /// polygon with the largest area is used.
+    ///
+    /// Returns `Point` geometry, or an error if the geometry is not a
+    /// non-empty polygon.
+    pub fn label_point(&self, precision: f64) -> Result<GeomData> {
+        if self.geom_type() != GeomType::Polygon {
+            return Err(Error::InvalidGeometry());
+        }
+        // Group rings into polygons: exterior followed by interior rings
+        let mut polygons: Vec<Vec<Path>> = Vec::new();
+        for ring in self.paths() {
+            match polygons.last_mut() {
+                Some(polygon) if ring_area(ring) < 0.0 => {
+                    polygon.push(ring.clone())
+                }
+                _ => polygons.push(vec![ring.clone()]),
+            }
+        }
+        let polygon = polygons
+            .iter()
+            .max_by(|a, b| ring_area(&a[0]).total_cmp(&ring_area(&b[0])))
+            .ok_or(Error::InvalidGeometry())?;
+        let (x, y) = polylabel(polygon, precision.max(MIN_PRECISION))
+            .ok_or(Error::InvalidGeometry())?;
+        GeomEncoder::new(GeomType::Point).point(x, y)?.encode()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_label_point() {
+        // U-shaped polygon, where the centroid is outside
+        let geom_data = GeomEncoder::new(GeomType::Polygon)
+            .point(0.0, 0.0)
+            .unwrap()
+            .point(30.0, 0.0)
+            .unwrap()
+            .point(30.0, 30.0)
+            .unwrap()
+            .point(20.0, 30.0)
+            .unwrap()
+            .point(20.0, 10.0)
+            .unwrap()
+            .point(10.0, 10.0)
+            .unwrap()
+            .point(10.0, 30.0)
+            .unwrap()
+            .point(0.0, 30.0)
+            .unwrap()
+            .encode()
+            .unwrap();
+        let label = geom_data.label_point(0.5).unwrap();
+        assert_eq!(label.geom_type(), GeomType::Point);
+        let (x, y) = label.centroid().unwrap();
+        assert!(signed_distance(x, y, geom_data.paths()) > 4.0);
+        for precision in [0.0, -1.0, f64::MIN_POSITIVE, f64::NAN] {
+            let label = geom_data.label_point(precision).unwrap();
+            let (x, y) = label.centroid().unwrap();
+            assert!(signed_distance(x, y, geom_data.paths()) > 4.0);
+        }
+    }
+
+    #[test]
+    fn test_label_point_invalid() {
+        let geom_data = GeomEncoder::new(GeomType::Linestring)
+            .point(0.0, 0.0)
+            .unwrap()
+            .point(30.0, 0.0)
+            .unwrap()
+            .encode()
+            .unwrap();
+        assert!(geom_data.label_point(1.0).is_err());
+    }
+}
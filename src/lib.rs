@@ -43,11 +43,37 @@
 //! [tile]: struct.Tile.html
 #![forbid(unsafe_code)]
 
+#[cfg(feature = "serde")]
+mod de;
+mod decoder;
+mod diff;
 mod encoder;
 mod error;
+mod extension;
+#[cfg(feature = "geozero")]
+mod geozero;
+mod label;
+mod measure;
+#[cfg(feature = "mvt3")]
+pub mod mvt3;
+#[cfg(feature = "raster")]
+mod raster;
+#[cfg(feature = "serde")]
+mod ser;
+#[cfg(feature = "svg")]
+mod svg;
 mod tile;
 mod vector_tile;
+#[cfg(feature = "wkb")]
+mod wkb;
 
-pub use crate::encoder::{GeomData, GeomEncoder, GeomType};
+pub use crate::diff::{FeatureKey, GeomChange, LayerDiff, TagChange, TileDiff};
+pub use crate::encoder::{GeomData, GeomEncoder, GeomType, Snap, TileBounds};
 pub use crate::error::Error;
-pub use crate::tile::{Feature, Layer, Tile};
+#[cfg(feature = "geozero")]
+pub use crate::geozero::LayerProcessor;
+#[cfg(feature = "raster")]
+pub use crate::raster::{LayerStyle, Raster, Rasterizer};
+pub use crate::tile::{
+    Feature, FeatureOrder, FeatureView, IdPolicy, Layer, Tile,
+};
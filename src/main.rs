@@ -1,9 +1,66 @@
 #![forbid(unsafe_code)]
 
+use mvt::Tile;
+use std::process::ExitCode;
+
+/// Usage text
+const USAGE: &str = "Usage: mvt diff [--tolerance <units>] <a.mvt> <b.mvt>";
+
 // Update vector tile module to new protobuf version
-fn main() {
+#[cfg(feature = "update")]
+fn update() -> ExitCode {
     prost_build::Config::new()
         .out_dir("src")
         .compile_protos(&["protos/vector_tile.proto"], &["protos"])
         .expect("Codegen failed");
+    ExitCode::SUCCESS
+}
+
+/// Read a tile file
+fn read_tile(path: &str) -> Result<Tile, String> {
+    let data = std::fs::read(path).map_err(|e| format!("{path}: {e}"))?;
+    Tile::from_bytes(&data).map_err(|e| format!("{path}: {e}"))
+}
+
+/// Compare two tile files
+fn diff(args: &[String]) -> Result<bool, String> {
+    let (tolerance, paths) = match args {
+        [opt, tol, paths @ ..] if opt == "--tolerance" => {
+            let tol = tol
+                .parse::<f64>()
+                .ok()
+                .filter(|t| t.is_finite() && *t >= 0.0)
+                .ok_or_else(|| format!("Invalid tolerance: {tol}"))?;
+            (tol, paths)
+        }
+        paths => (0.0, paths),
+    };
+    let [a, b] = paths else {
+        return Err(USAGE.to_string());
+    };
+    let diff = read_tile(a)?
+        .diff(&read_tile(b)?, tolerance)
+        .map_err(|e| e.to_string())?;
+    print!("{diff}");
+    Ok(diff.is_empty())
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.split_first() {
+        Some((cmd, args)) if cmd == "diff" => match diff(args) {
+            Ok(true) => ExitCode::SUCCESS,
+            Ok(false) => ExitCode::from(1),
+            Err(e) => {
+                eprintln!("{e}");
+                ExitCode::from(2)
+            }
+        },
+        #[cfg(feature = "update")]
+        None => update(),
+        _ => {
+            eprintln!("{USAGE}");
+            ExitCode::from(2)
+        }
+    }
 }
@@ -0,0 +1,167 @@
+// measure.rs
+//
+// Copyright (c) 2019-2026  Minnesota Department of Transportation
+//
+//! Per-vertex measures (M values), stored in extension fields.
+//!
+//! Measures added with
+//! [GeomEncoder::add_point_m](struct.GeomEncoder.html#method.add_point_m)
+//! are encoded in feature field 8192, as packed varints.  Each value is
+//! `round(m * scale)`, delta and zigzag encoded from the previous vertex
+//! (like geometry parameters).  Vertices without a measure repeat the
+//! previous value.
+//!
+//! If the scale is not 1, it is stored in layer field 8192 (double).
+//!
+//! These field numbers are outside the range for user extensions (16 to
+//! 8191), and are rejected by
+//! [Layer::add_extension](struct.Layer.html#method.add_extension) and
+//! [Feature::add_extension](struct.Feature.html#method.add_extension).
+use crate::encoder::GeomData;
+use crate::extension::{
+    WIRE_I64, WIRE_LEN, find_field, read_packed, unzigzag, write_field,
+    write_varint, zigzag,
+};
+use crate::tile::{FeatureView, Layer};
+
+/// Feature field number of measures (reserved)
+pub(crate) const FEATURE_MEASURES: u32 = 8192;
+
+/// Layer field number of measure scale (reserved)
+pub(crate) const LAYER_MEASURE_SCALE: u32 = 8192;
+
+impl Layer {
+    /// Set the measure scale.
+    ///
+    /// * `scale` Multiplier applied to measures before rounding to integers.
+    ///
+    /// For example, a scale of 100 preserves two decimal places.
+    pub fn measure_scale(mut self, scale: f64) -> Self {
+        self.measure_scale = scale;
+        self
+    }
+
+    /// Encode the measure scale into layer fields
+    pub(crate) fn encode_measure_scale(mut self) -> Self {
+        if self.measure_scale != 1.0 {
+            write_varint(
+                &mut self.ext.fields,
+                (u64::from(LAYER_MEASURE_SCALE) << 3) | WIRE_I64,
+            );
+            self.ext
+                .fields
+                .extend_from_slice(&self.measure_scale.to_le_bytes());
+        }
+        self
+    }
+
+    /// Encode measures of geometry into feature fields
+    pub(crate) fn encode_measures(&self, geom_data: &GeomData) -> Vec<u8> {
+        let mut ext = Vec::new();
+        let Some(measures) = geom_data.measures() else {
+            return ext;
+        };
+        let mut payload = Vec::new();
+        let mut prev = 0;
+        for m in measures {
+            let val = if m.is_nan() {
+                prev
+            } else {
+                (m * self.measure_scale).round() as i64
+            };
+            write_varint(&mut payload, zigzag(val.wrapping_sub(prev)));
+            prev = val;
+        }
+        write_field(&mut ext, FEATURE_MEASURES, &payload);
+        ext
+    }
+}
+
+impl FeatureView<'_> {
+    /// Get the measure (M value) of each vertex.
+    ///
+    /// Returns `None` if the feature has no (valid) measures.
+    ///
+    /// # Example
+    /// ```
+    /// # use mvt::{Error, GeomEncoder, GeomType, Tile};
+    /// # fn main() -> Result<(), Error> {
+    /// let mut tile = Tile::new(4096);
+    /// let layer = tile.create_layer("tracks").measure_scale(10.0);
+    /// let geom_data = GeomEncoder::new(GeomType::Linestring)
+    ///     .point_m(0.0, 0.0, 12.5)?
+    ///     .point_m(10.0, 0.0, 13.0)?
+    ///     .encode()?;
+    /// tile.add_layer(layer.into_feature(geom_data).into_layer())?;
+    /// let tile = Tile::from_bytes(&tile.to_bytes()?)?;
+    /// let feature = tile.features("tracks").next().unwrap();
+    /// assert_eq!(feature.measures(), Some(vec![12.5, 13.0]));
+    /// # Ok(()) }
+    /// ```
+    pub fn measures(&self) -> Option<Vec<f64>> {
+        let payload =
+            find_field(self.extensions(), FEATURE_MEASURES, WIRE_LEN)?;
+        let scale = match find_field(
+            self.layer_ext_fields(),
+            LAYER_MEASURE_SCALE,
+            WIRE_I64,
+        ) {
+            Some(bytes) => f64::from_le_bytes(bytes.try_into().ok()?),
+            None => 1.0,
+        };
+        let mut val = 0i64;
+        let measures = read_packed(payload)?
+            .into_iter()
+            .map(|delta| {
+                val = val.wrapping_add(unzigzag(delta));
+                val as f64 / scale
+            })
+            .collect();
+        Some(measures)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::encoder::{GeomEncoder, GeomType};
+    use crate::tile::Tile;
+
+    #[test]
+    fn test_measures() {
+        let mut tile = Tile::new(256);
+        let layer = tile.create_layer("tracks");
+        let geom_data = GeomEncoder::new(GeomType::Linestring)
+            .point_m(0.0, 10.0, 0.0)
+            .unwrap()
+            .point_m(10.0, 10.0, 20.0)
+            .unwrap()
+            .point(20.0, 20.0)
+            .unwrap()
+            .point_m(30.0, 30.0, 3.0)
+            .unwrap()
+            .encode()
+            .unwrap();
+        let layer = layer.into_feature(geom_data).into_layer();
+        // deltas: 0, 20, 0 (repeated), -17
+        assert_eq!(layer.ext.features[&0], [130, 128, 4, 4, 0, 40, 0, 33]);
+        tile.add_layer(layer).unwrap();
+        let tile = Tile::from_bytes(&tile.to_bytes().unwrap()).unwrap();
+        let feature = tile.features("tracks").next().unwrap();
+        assert_eq!(feature.measures(), Some(vec![0.0, 20.0, 20.0, 3.0]));
+    }
+
+    #[test]
+    fn test_no_measures() {
+        let mut tile = Tile::new(256);
+        let layer = tile.create_layer("roads").measure_scale(100.0);
+        let geom_data = GeomEncoder::new(GeomType::Point)
+            .point(1.0, 2.0)
+            .unwrap()
+            .encode()
+            .unwrap();
+        tile.add_layer(layer.into_feature(geom_data).into_layer())
+            .unwrap();
+        let feature = tile.features("roads").next().unwrap();
+        assert_eq!(feature.measures(), None);
+    }
+}
@@ -0,0 +1,479 @@
+// mvt3.rs
+//
+// Copyright (c) 2019-2026  Minnesota Department of Transportation
+//
+//! Experimental support for MVT version 3 draft features.
+//!
+//! This module is enabled by the `mvt3` feature.  The version 3
+//! specification is not finalized, so the encoding may change in future
+//! releases.
+//!
+//! Version 3 layers are created with
+//! [Tile::create_layer_v3](../struct.Tile.html#method.create_layer_v3), and
+//! support:
+//! - Inline attributes, including list and map values, with
+//!   [Feature::add_attribute](../struct.Feature.html#method.add_attribute)
+//! - Per-vertex elevation, with
+//!   [GeomEncoder::add_point_z](../struct.GeomEncoder.html#method.add_point_z)
+//!
+//! Layers created with [Tile::create_layer](../struct.Tile.html#method.create_layer)
+//! are always version 2.
+//!
+//! Inline attributes are encoded as pairs of a key index and a complex
+//! value.  The low 4 bits of a complex value are its type, and the high bits
+//! a parameter:
+//!
+//! | Type | Value          | Parameter                              |
+//! |------|----------------|----------------------------------------|
+//! | 0    | string         | index in layer `string_values`         |
+//! | 1    | float          | index in layer `float_values`          |
+//! | 2    | double         | index in layer `double_values`         |
+//! | 3    | int            | index in layer `int_values`            |
+//! | 4    | uint           | inline value                           |
+//! | 5    | sint           | inline value (zigzag)                  |
+//! | 6    | bool / null    | 0: false, 1: true, 2: null             |
+//! | 7    | list           | count of values (which follow)         |
+//! | 8    | map            | count of key / value pairs (following) |
+use crate::encoder::{GeomData, GeomEncoder};
+use crate::error::{Error, Result};
+use crate::extension::{
+    WIRE_LEN, find_field, write_field, write_varint, zigzag,
+};
+use crate::tile::{Feature, Layer, Tile};
+use pointy::Float;
+use std::collections::HashMap;
+
+/// Layer field number of string values
+const LAYER_STRING_VALUES: u32 = 7;
+
+/// Layer field number of float values
+const LAYER_FLOAT_VALUES: u32 = 8;
+
+/// Layer field number of double values
+const LAYER_DOUBLE_VALUES: u32 = 9;
+
+/// Layer field number of int values
+const LAYER_INT_VALUES: u32 = 10;
+
+/// Layer field number of elevation scaling
+const LAYER_ELEVATION_SCALING: u32 = 11;
+
+/// Feature field number of inline attributes
+const FEATURE_ATTRIBUTES: u32 = 5;
+
+/// Feature field number of elevations
+pub(crate) const FEATURE_ELEVATIONS: u32 = 7;
+
+/// Maximum value of an inline parameter
+const MAX_PARAM: u64 = (1 << 60) - 1;
+
+/// Attribute value (version 3 layers).
+#[derive(Clone, Debug, PartialEq)]
+pub enum AttrValue {
+    /// String value
+    String(String),
+    /// Float value
+    Float(f32),
+    /// Double value
+    Double(f64),
+    /// Signed integer value
+    Int(i64),
+    /// Unsigned integer value
+    Uint(u64),
+    /// Boolean value
+    Bool(bool),
+    /// Null value
+    Null,
+    /// List of values
+    List(Vec<AttrValue>),
+    /// Map of keys to values
+    Map(Vec<(String, AttrValue)>),
+}
+
+impl AttrValue {
+    /// Check if all unsigned values can be encoded inline
+    fn is_valid(&self) -> bool {
+        match self {
+            AttrValue::Uint(v) => *v <= MAX_PARAM,
+            AttrValue::List(vals) => vals.iter().all(AttrValue::is_valid),
+            AttrValue::Map(pairs) => pairs.iter().all(|(_k, v)| v.is_valid()),
+            _ => true,
+        }
+    }
+}
+
+/// Scaling of integer values (version 3 layers).
+///
+/// Values are encoded as `round((value - base) / multiplier) - offset`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Scaling {
+    /// Offset of encoded values
+    pub offset: i64,
+    /// Multiplier of encoded values
+    pub multiplier: f64,
+    /// Base value
+    pub base: f64,
+}
+
+impl Default for Scaling {
+    fn default() -> Self {
+        Scaling {
+            offset: 0,
+            multiplier: 1.0,
+            base: 0.0,
+        }
+    }
+}
+
+impl Scaling {
+    /// Scale a value
+    fn scale(&self, value: f64) -> i64 {
+        ((value - self.base) / self.multiplier).round() as i64 - self.offset
+    }
+
+    /// Encode scaling message
+    fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        write_varint(&mut buf, 1 << 3);
+        write_varint(&mut buf, zigzag(self.offset));
+        write_varint(&mut buf, (2 << 3) | 1);
+        buf.extend_from_slice(&self.multiplier.to_le_bytes());
+        write_varint(&mut buf, (3 << 3) | 1);
+        buf.extend_from_slice(&self.base.to_le_bytes());
+        buf
+    }
+}
+
+/// Value pools of a version 3 layer
+#[derive(Default)]
+pub(crate) struct Pools {
+    /// String values
+    strings: Vec<String>,
+
+    /// Indices of string values
+    string_indices: HashMap<String, usize>,
+
+    /// Float values
+    floats: Vec<f32>,
+
+    /// Double values
+    doubles: Vec<f64>,
+
+    /// Int values
+    ints: Vec<i64>,
+
+    /// Elevation scaling
+    elevation_scaling: Scaling,
+}
+
+impl Pools {
+    /// Get position of a string value, adding it if not found
+    fn string_pos(&mut self, val: &str) -> usize {
+        if let Some(&index) = self.string_indices.get(val) {
+            return index;
+        }
+        let index = self.strings.len();
+        self.strings.push(val.to_string());
+        self.string_indices.insert(val.to_string(), index);
+        index
+    }
+
+    /// Encode pools as layer fields
+    fn encode(&self, buf: &mut Vec<u8>) {
+        for val in &self.strings {
+            write_field(buf, LAYER_STRING_VALUES, val.as_bytes());
+        }
+        if !self.floats.is_empty() {
+            let payload: Vec<u8> =
+                self.floats.iter().flat_map(|v| v.to_le_bytes()).collect();
+            write_field(buf, LAYER_FLOAT_VALUES, &payload);
+        }
+        if !self.doubles.is_empty() {
+            let payload: Vec<u8> =
+                self.doubles.iter().flat_map(|v| v.to_le_bytes()).collect();
+            write_field(buf, LAYER_DOUBLE_VALUES, &payload);
+        }
+        if !self.ints.is_empty() {
+            let payload: Vec<u8> =
+                self.ints.iter().flat_map(|v| v.to_le_bytes()).collect();
+            write_field(buf, LAYER_INT_VALUES, &payload);
+        }
+        if self.elevation_scaling != Scaling::default() {
+            let payload = self.elevation_scaling.encode();
+            write_field(buf, LAYER_ELEVATION_SCALING, &payload);
+        }
+    }
+}
+
+/// Make a complex value
+fn complex(tp: u64, param: u64) -> u64 {
+    (param << 4) | tp
+}
+
+impl Tile {
+    /// Create a new version 3 layer (experimental).
+    ///
+    /// * `name` Layer name.
+    pub fn create_layer_v3(&self, name: &str) -> Layer {
+        let mut layer = self.create_layer(name);
+        layer.layer.version = 3;
+        layer
+    }
+}
+
+impl Layer {
+    /// Set the elevation scaling (version 3 layers).
+    ///
+    /// * `scaling` Scaling of elevation values.
+    pub fn elevation_scaling(mut self, scaling: Scaling) -> Self {
+        self.pools.elevation_scaling = scaling;
+        self
+    }
+
+    /// Encode value pools into layer fields
+    pub(crate) fn encode_pools(mut self) -> Self {
+        if self.layer.version == 3 {
+            let pools = std::mem::take(&mut self.pools);
+            pools.encode(&mut self.ext.fields);
+        }
+        self
+    }
+
+    /// Encode elevations of geometry into feature fields
+    pub(crate) fn encode_elevations(&self, geom_data: &GeomData) -> Vec<u8> {
+        let mut ext = Vec::new();
+        if self.layer.version != 3 {
+            return ext;
+        }
+        let Some(elevations) = geom_data.elevations() else {
+            return ext;
+        };
+        let scaling = self.pools.elevation_scaling;
+        let mut payload = Vec::new();
+        let mut prev = 0;
+        for z in elevations {
+            let val = if z.is_nan() { prev } else { scaling.scale(*z) };
+            write_varint(&mut payload, zigzag(val.wrapping_sub(prev)));
+            prev = val;
+        }
+        write_field(&mut ext, FEATURE_ELEVATIONS, &payload);
+        ext
+    }
+
+    /// Check if any features have inline attributes
+    pub(crate) fn has_attributes(&self) -> bool {
+        self.ext
+            .features
+            .values()
+            .any(|ext| find_field(ext, FEATURE_ATTRIBUTES, WIRE_LEN).is_some())
+    }
+
+    /// Encode an attribute value
+    fn encode_attr(&mut self, value: &AttrValue, attrs: &mut Vec<u64>) {
+        let pools = &mut self.pools;
+        match value {
+            AttrValue::String(v) => {
+                attrs.push(complex(0, pools.string_pos(v) as u64));
+            }
+            AttrValue::Float(v) => {
+                attrs.push(complex(1, pools.floats.len() as u64));
+                pools.floats.push(*v);
+            }
+            AttrValue::Double(v) => {
+                attrs.push(complex(2, pools.doubles.len() as u64));
+                pools.doubles.push(*v);
+            }
+            AttrValue::Int(v) => {
+                let zz = zigzag(*v);
+                if zz <= MAX_PARAM {
+                    attrs.push(complex(5, zz));
+                } else {
+                    attrs.push(complex(3, pools.ints.len() as u64));
+                    pools.ints.push(*v);
+                }
+            }
+            AttrValue::Uint(v) => attrs.push(complex(4, *v)),
+            AttrValue::Bool(v) => attrs.push(complex(6, u64::from(*v))),
+            AttrValue::Null => attrs.push(complex(6, 2)),
+            AttrValue::List(vals) => {
+                attrs.push(complex(7, vals.len() as u64));
+                for val in vals {
+                    self.encode_attr(val, attrs);
+                }
+            }
+            AttrValue::Map(pairs) => {
+                attrs.push(complex(8, pairs.len() as u64));
+                for (key, val) in pairs {
+                    attrs.push(self.key_pos(key) as u64);
+                    self.encode_attr(val, attrs);
+                }
+            }
+        }
+    }
+}
+
+impl Feature {
+    /// Add an inline attribute (version 3 layers).
+    ///
+    /// * `key` Attribute key.
+    /// * `value` Attribute value.
+    ///
+    /// Returns an error if the layer is not version 3, or an unsigned value
+    /// is too large (60 bits maximum).
+    ///
+    /// # Example
+    /// ```
+    /// # use mvt::{Error, GeomEncoder, GeomType, Tile};
+    /// # use mvt::mvt3::AttrValue;
+    /// # fn main() -> Result<(), Error> {
+    /// let mut tile = Tile::new(4096);
+    /// let layer = tile.create_layer_v3("buildings");
+    /// let geom_data = GeomEncoder::new(GeomType::Point)
+    ///     .point_z(1.0, 2.0, 250.0)?
+    ///     .encode()?;
+    /// let mut feature = layer.into_feature(geom_data);
+    /// feature.add_attribute(
+    ///     "floors",
+    ///     &AttrValue::List(vec![AttrValue::Uint(1), AttrValue::Uint(2)]),
+    /// )?;
+    /// tile.add_layer(feature.into_layer())?;
+    /// # Ok(()) }
+    /// ```
+    pub fn add_attribute(
+        &mut self,
+        key: &str,
+        value: &AttrValue,
+    ) -> Result<()> {
+        if self.layer.layer.version != 3 {
+            return Err(Error::WrongVersion());
+        }
+        if !value.is_valid() {
+            return Err(Error::InvalidValue());
+        }
+        let mut attrs = vec![self.layer.key_pos(key) as u64];
+        self.layer.encode_attr(value, &mut attrs);
+        self.attributes.extend(attrs);
+        Ok(())
+    }
+
+    /// Encode inline attributes into feature fields
+    pub(crate) fn encode_attributes(&mut self) {
+        if !self.attributes.is_empty() {
+            let mut payload = Vec::new();
+            for attr in &self.attributes {
+                write_varint(&mut payload, *attr);
+            }
+            let index = self.layer.layer.features.len();
+            let ext = self.layer.ext.features.entry(index).or_default();
+            write_field(ext, FEATURE_ATTRIBUTES, &payload);
+        }
+    }
+}
+
+impl<F> GeomEncoder<F>
+where
+    F: Float,
+{
+    /// Add a point with elevation, taking ownership (for method chaining).
+    pub fn point_z(mut self, x: F, y: F, z: F) -> Result<Self> {
+        self.add_point_z(x, y, z)?;
+        Ok(self)
+    }
+
+    /// Add a point with elevation (version 3 layers).
+    ///
+    /// * `z` Elevation, which is encoded using the layer's
+    ///   [elevation_scaling](struct.Layer.html#method.elevation_scaling).
+    ///
+    /// Elevations are interpolated for boundary points added by clipping.
+    pub fn add_point_z(&mut self, x: F, y: F, z: F) -> Result<()> {
+        let z = z.to_f64().ok_or(Error::InvalidValue())?;
+        self.add_point_elevation(x, y, z)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::encoder::GeomType;
+
+    fn point_feature(layer: Layer) -> Feature {
+        let geom_data = GeomEncoder::new(GeomType::Linestring)
+            .point_z(0.0, 0.0, 10.0)
+            .unwrap()
+            .point_z(10.0, 0.0, 12.0)
+            .unwrap()
+            .point_z(10.0, 10.0, 11.0)
+            .unwrap()
+            .encode()
+            .unwrap();
+        layer.into_feature(geom_data)
+    }
+
+    #[test]
+    fn test_v2_layer() {
+        let tile = Tile::new(4096);
+        let mut feature = point_feature(tile.create_layer("v2"));
+        assert!(matches!(
+            feature.add_attribute("a", &AttrValue::Null),
+            Err(Error::WrongVersion())
+        ));
+        let layer = feature.into_layer();
+        assert_eq!(layer.layer.version, 2);
+        assert!(layer.ext.is_empty());
+    }
+
+    #[test]
+    fn test_elevations() {
+        let tile = Tile::new(4096);
+        let layer = tile.create_layer_v3("v3").elevation_scaling(Scaling {
+            offset: 0,
+            multiplier: 0.5,
+            base: 0.0,
+        });
+        let layer = point_feature(layer).into_layer();
+        // elevations: 20, 24, 22 => deltas 20, 4, -2
+        assert_eq!(layer.ext.features[&0], [58, 3, 40, 8, 3]);
+    }
+
+    #[test]
+    fn test_attributes() {
+        let mut tile = Tile::new(4096);
+        let mut feature = point_feature(tile.create_layer_v3("v3"));
+        feature
+            .add_attribute(
+                "info",
+                &AttrValue::Map(vec![
+                    ("name".to_string(), AttrValue::String("x".to_string())),
+                    ("height".to_string(), AttrValue::Double(2.5)),
+                    ("offset".to_string(), AttrValue::Int(-1)),
+                    ("open".to_string(), AttrValue::Bool(true)),
+                ]),
+            )
+            .unwrap();
+        assert!(
+            feature
+                .add_attribute("big", &AttrValue::Uint(u64::MAX))
+                .is_err()
+        );
+        let mut layer = feature.into_layer();
+        assert_eq!(layer.optimize(), 0);
+        assert_eq!(
+            layer.layer.keys,
+            ["info", "name", "height", "offset", "open"]
+        );
+        let ext = &layer.ext.features[&0];
+        // elevations field first, then attributes
+        let attrs = &ext[ext.len() - 12..];
+        assert_eq!(attrs, [42, 10, 0, 0x48, 1, 0, 2, 2, 3, 0x15, 4, 0x16]);
+        let layer = layer.encode_pools();
+        assert_eq!(
+            &layer.ext.fields[..3],
+            &[(LAYER_STRING_VALUES << 3 | 2) as u8, 1, b'x']
+        );
+        let fields = layer.ext.fields.clone();
+        tile.add_layer(layer).unwrap();
+        let tile = Tile::from_bytes(&tile.to_bytes().unwrap()).unwrap();
+        assert_eq!(tile.layer_extensions("v3"), fields);
+    }
+}
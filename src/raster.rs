@@ -0,0 +1,458 @@
+// raster.rs
+//
+// Copyright (c) 2019-2026  Minnesota Department of Transportation
+//
+//! Software rasterizer for previewing tiles.
+//!
+use crate::decoder::{Path, decode_paths_version};
+use crate::encoder::GeomType;
+use crate::error::Result;
+use crate::tile::{Tile, feature_geom_type};
+use crate::vector_tile::tile::Layer as VtLayer;
+use std::collections::HashMap;
+
+/// Style for rendering a layer.
+///
+/// Colors are RGBA, with non-premultiplied alpha.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LayerStyle {
+    /// Fill color of polygons and points
+    pub fill: [u8; 4],
+    /// Stroke color of linestrings and polygon outlines
+    pub stroke: [u8; 4],
+    /// Width of lines (pixels); polygons are not outlined if zero
+    pub line_width: f32,
+    /// Radius of points (pixels)
+    pub point_radius: f32,
+}
+
+/// Software rasterizer for tiles.
+///
+/// Rendering is deterministic and not anti-aliased, so it is suitable for
+/// comparing against golden images.  Polygons are filled using the nonzero
+/// winding rule, so holes with the wrong winding order are filled.
+///
+/// # Example
+/// ```
+/// # use mvt::{Error, GeomEncoder, GeomType, LayerStyle, Rasterizer, Tile};
+/// # fn main() -> Result<(), Error> {
+/// let mut tile = Tile::new(4096);
+/// let layer = tile.create_layer("roads");
+/// let geom_data = GeomEncoder::new(GeomType::Linestring)
+///     .point(0.0, 0.0)?
+///     .point(4096.0, 4096.0)?
+///     .encode()?;
+/// tile.add_layer(layer.into_feature(geom_data).into_layer())?;
+/// let style = LayerStyle {
+///     line_width: 3.0,
+///     ..Default::default()
+/// };
+/// let raster = Rasterizer::new(256).style("roads", style).render(&tile)?;
+/// assert_eq!(raster.pixel(128, 128), style.stroke);
+/// let png = raster.to_png()?;
+/// # Ok(()) }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Rasterizer {
+    /// Width / height (pixels)
+    size: u32,
+
+    /// Background color
+    background: [u8; 4],
+
+    /// Style for layers without a specific style
+    default_style: LayerStyle,
+
+    /// Styles by layer name
+    styles: HashMap<String, LayerStyle>,
+}
+
+/// Rendered RGBA image.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Raster {
+    /// Width (pixels)
+    width: u32,
+
+    /// Height (pixels)
+    height: u32,
+
+    /// RGBA pixel data
+    pixels: Vec<u8>,
+}
+
+/// Mask of pixels covered by one feature
+struct Mask {
+    /// Width (pixels)
+    width: usize,
+
+    /// Height (pixels)
+    height: usize,
+
+    /// Covered pixels
+    covered: Vec<bool>,
+
+    /// Indices of covered pixels
+    touched: Vec<usize>,
+}
+
+impl Default for LayerStyle {
+    fn default() -> Self {
+        LayerStyle {
+            fill: [31, 119, 180, 128],
+            stroke: [31, 119, 180, 255],
+            line_width: 1.0,
+            point_radius: 2.0,
+        }
+    }
+}
+
+impl Mask {
+    /// Create a new mask
+    fn new(width: usize, height: usize) -> Self {
+        Mask {
+            width,
+            height,
+            covered: vec![false; width * height],
+            touched: Vec::new(),
+        }
+    }
+
+    /// Mark a pixel as covered
+    fn set(&mut self, x: usize, y: usize) {
+        let i = y * self.width + x;
+        if !self.covered[i] {
+            self.covered[i] = true;
+            self.touched.push(i);
+        }
+    }
+
+    /// Get range of pixel indices with centers within a span
+    fn span(v0: f64, v1: f64, len: usize) -> std::ops::Range<usize> {
+        let start = (v0 - 0.5).ceil().clamp(0.0, len as f64) as usize;
+        let end = (v1 - 0.5).floor().clamp(-1.0, len as f64 - 1.0) + 1.0;
+        start..(end as usize).max(start)
+    }
+
+    /// Fill polygon rings, using the nonzero winding rule
+    fn fill_rings(&mut self, rings: &[Vec<(f64, f64)>]) {
+        let mut crossings = Vec::new();
+        for y in 0..self.height {
+            let yc = y as f64 + 0.5;
+            crossings.clear();
+            for ring in rings {
+                let n = ring.len();
+                for i in 0..n {
+                    let (x0, y0) = ring[i];
+                    let (x1, y1) = ring[(i + 1) % n];
+                    if (y0 <= yc) != (y1 <= yc) {
+                        let x = x0 + (yc - y0) * (x1 - x0) / (y1 - y0);
+                        crossings.push((x, if y1 > y0 { 1 } else { -1 }));
+                    }
+                }
+            }
+            crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+            let mut winding = 0;
+            for pair in crossings.windows(2) {
+                winding += pair[0].1;
+                if winding != 0 {
+                    for x in Self::span(pair[0].0, pair[1].0, self.width) {
+                        self.set(x, y);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Stroke a line segment
+    fn stroke_segment(&mut self, p0: (f64, f64), p1: (f64, f64), hw: f64) {
+        let (dx, dy) = (p1.0 - p0.0, p1.1 - p0.1);
+        let len2 = dx * dx + dy * dy;
+        let xs =
+            Self::span(p0.0.min(p1.0) - hw, p0.0.max(p1.0) + hw, self.width);
+        let ys =
+            Self::span(p0.1.min(p1.1) - hw, p0.1.max(p1.1) + hw, self.height);
+        for y in ys {
+            let yc = y as f64 + 0.5;
+            for x in xs.clone() {
+                let xc = x as f64 + 0.5;
+                let t = if len2 > 0.0 {
+                    (((xc - p0.0) * dx + (yc - p0.1) * dy) / len2)
+                        .clamp(0.0, 1.0)
+                } else {
+                    0.0
+                };
+                let (ex, ey) = (p0.0 + t * dx - xc, p0.1 + t * dy - yc);
+                if ex * ex + ey * ey <= hw * hw {
+                    self.set(x, y);
+                }
+            }
+        }
+    }
+
+    /// Stroke a path
+    fn stroke_path(&mut self, path: &[(f64, f64)], hw: f64, close: bool) {
+        for seg in path.windows(2) {
+            self.stroke_segment(seg[0], seg[1], hw);
+        }
+        if let ([.., last], [first, ..]) = (path, path)
+            && close
+        {
+            self.stroke_segment(*last, *first, hw);
+        }
+    }
+
+    /// Blend covered pixels with a color, and clear the mask
+    fn blend(&mut self, pixels: &mut [u8], color: [u8; 4]) {
+        let sa = u32::from(color[3]);
+        for i in self.touched.drain(..) {
+            self.covered[i] = false;
+            let px = &mut pixels[i * 4..i * 4 + 4];
+            let da = u32::from(px[3]) * (255 - sa);
+            // output alpha, scaled by 255
+            let oa = sa * 255 + da;
+            if oa == 0 {
+                continue;
+            }
+            for c in 0..3 {
+                let src = u32::from(color[c]) * sa * 255;
+                let dst = u32::from(px[c]) * da;
+                px[c] = ((src + dst + oa / 2) / oa) as u8;
+            }
+            px[3] = ((oa + 127) / 255) as u8;
+        }
+    }
+}
+
+impl Rasterizer {
+    /// Create a new rasterizer.
+    ///
+    /// * `size` Width / height of rendered images (pixels).
+    pub fn new(size: u32) -> Self {
+        Rasterizer {
+            size,
+            background: [255; 4],
+            default_style: LayerStyle::default(),
+            styles: HashMap::new(),
+        }
+    }
+
+    /// Set the background color (default opaque white).
+    pub fn background(mut self, color: [u8; 4]) -> Self {
+        self.background = color;
+        self
+    }
+
+    /// Set the style for layers without a specific style.
+    pub fn default_style(mut self, style: LayerStyle) -> Self {
+        self.default_style = style;
+        self
+    }
+
+    /// Set the style for a layer.
+    ///
+    /// * `name` Layer name.
+    /// * `style` Layer style.
+    pub fn style(mut self, name: &str, style: LayerStyle) -> Self {
+        self.styles.insert(name.to_string(), style);
+        self
+    }
+
+    /// Render a tile.
+    ///
+    /// Layers are drawn in order, scaled from their extent to the image size.
+    ///
+    /// Returns an error if a feature contains invalid geometry.
+    pub fn render(&self, tile: &Tile) -> Result<Raster> {
+        let size = self.size as usize;
+        let mut pixels = self.background.repeat(size * size);
+        let mut mask = Mask::new(size, size);
+        for layer in tile.all_layers()? {
+            let style =
+                self.styles.get(&layer.name).unwrap_or(&self.default_style);
+            self.render_layer(&layer, style, &mut mask, &mut pixels)?;
+        }
+        Ok(Raster {
+            width: self.size,
+            height: self.size,
+            pixels,
+        })
+    }
+
+    /// Render one layer
+    fn render_layer(
+        &self,
+        layer: &VtLayer,
+        style: &LayerStyle,
+        mask: &mut Mask,
+        pixels: &mut [u8],
+    ) -> Result<()> {
+        let extent = layer.extent.unwrap_or(4096).max(1);
+        let scale = f64::from(self.size) / f64::from(extent);
+        let hw = (f64::from(style.line_width) / 2.0).max(0.5);
+        let radius = f64::from(style.point_radius);
+        for feature in &layer.features {
+            let Some(geom_tp) = feature_geom_type(feature) else {
+                continue;
+            };
+            let paths: Vec<Vec<(f64, f64)>> = decode_paths_version(
+                geom_tp,
+                layer.version,
+                &feature.geometry,
+            )?
+            .iter()
+            .map(|path| scale_path(path, scale))
+            .collect();
+            match geom_tp {
+                GeomType::Point => {
+                    for pt in paths.iter().flatten() {
+                        mask.stroke_segment(*pt, *pt, radius);
+                    }
+                    mask.blend(pixels, style.fill);
+                }
+                GeomType::Linestring => {
+                    for path in &paths {
+                        mask.stroke_path(path, hw, false);
+                    }
+                    mask.blend(pixels, style.stroke);
+                }
+                GeomType::Polygon => {
+                    mask.fill_rings(&paths);
+                    mask.blend(pixels, style.fill);
+                    if style.line_width > 0.0 {
+                        for path in &paths {
+                            mask.stroke_path(path, hw, true);
+                        }
+                        mask.blend(pixels, style.stroke);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Scale a path from tile units to pixels
+fn scale_path(path: &Path, scale: f64) -> Vec<(f64, f64)> {
+    path.iter()
+        .map(|(x, y)| (f64::from(*x) * scale, f64::from(*y) * scale))
+        .collect()
+}
+
+impl Raster {
+    /// Get the width (pixels).
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Get the height (pixels).
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Get RGBA pixel data (row-major, 4 bytes per pixel).
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Get the RGBA color of one pixel.
+    ///
+    /// Panics if the pixel is out of bounds.
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        assert!(x < self.width && y < self.height, "pixel out of bounds");
+        let i = (y as usize * self.width as usize + x as usize) * 4;
+        [
+            self.pixels[i],
+            self.pixels[i + 1],
+            self.pixels[i + 2],
+            self.pixels[i + 3],
+        ]
+    }
+
+    /// Encode as a PNG image.
+    pub fn to_png(&self) -> Result<Vec<u8>> {
+        let mut data = Vec::new();
+        let mut encoder = png::Encoder::new(&mut data, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.pixels)?;
+        writer.finish()?;
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::encoder::GeomEncoder;
+
+    /// Make a tile with a polygon, with a hole
+    fn polygon_tile(hole_reversed: bool) -> Tile {
+        let mut tile = Tile::new(64);
+        let layer = tile.create_layer("polygons");
+        let mut enc = GeomEncoder::new(GeomType::Polygon)
+            .point(8.0, 8.0)
+            .unwrap()
+            .point(8.0, 56.0)
+            .unwrap()
+            .point(56.0, 56.0)
+            .unwrap()
+            .point(56.0, 8.0)
+            .unwrap()
+            .complete()
+            .unwrap();
+        let mut hole = [(24.0, 24.0), (40.0, 24.0), (40.0, 40.0), (24.0, 40.0)];
+        if hole_reversed {
+            hole.reverse();
+        }
+        for (x, y) in hole {
+            enc.add_point(x, y).unwrap();
+        }
+        let geom_data = enc.encode().unwrap();
+        tile.add_layer(layer.into_feature(geom_data).into_layer())
+            .unwrap();
+        tile
+    }
+
+    #[test]
+    fn test_polygon() {
+        let fill = [255, 0, 0, 255];
+        let style = LayerStyle {
+            fill,
+            line_width: 0.0,
+            ..Default::default()
+        };
+        let rasterizer = Rasterizer::new(64).style("polygons", style);
+        let raster = rasterizer.render(&polygon_tile(false)).unwrap();
+        assert_eq!((raster.width(), raster.height()), (64, 64));
+        assert_eq!(raster.pixel(4, 4), [255; 4]);
+        assert_eq!(raster.pixel(12, 12), fill);
+        assert_eq!(raster.pixel(32, 32), [255; 4]);
+        // wrong winding order fills the hole
+        let raster = rasterizer.render(&polygon_tile(true)).unwrap();
+        assert_eq!(raster.pixel(32, 32), fill);
+    }
+
+    #[test]
+    fn test_blend() {
+        let style = LayerStyle {
+            fill: [0, 0, 255, 128],
+            line_width: 0.0,
+            ..Default::default()
+        };
+        let raster = Rasterizer::new(32)
+            .background([0; 4])
+            .default_style(style)
+            .render(&polygon_tile(false))
+            .unwrap();
+        assert_eq!(raster.pixel(6, 6), [0, 0, 255, 128]);
+        assert_eq!(raster.pixel(0, 0), [0; 4]);
+    }
+
+    #[test]
+    fn test_png() {
+        let raster = Rasterizer::new(16).render(&polygon_tile(false)).unwrap();
+        let png = raster.to_png().unwrap();
+        assert_eq!(png[..8], [0x89, b'P', b'N', b'G', 13, 10, 26, 10]);
+    }
+}
@@ -0,0 +1,686 @@
+// ser.rs
+//
+// Copyright (c) 2019-2026  Minnesota Department of Transportation
+//
+//! Serialization of feature tags with serde.
+//!
+use crate::error::{Error, Result};
+use crate::tile::Feature;
+use serde::Serialize;
+use serde::ser::{self, Impossible};
+use std::fmt::Display;
+
+impl ser::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        Error::Serde(msg.to_string())
+    }
+}
+
+impl Feature {
+    /// Add tags from a serializable struct or map.
+    ///
+    /// * `value` Struct or map containing tags.
+    ///
+    /// Values are mapped to tags:
+    /// - Integers: `uint` if non-negative, otherwise `sint`
+    /// - Floats: `double`
+    /// - Strings, chars and unit enum variants: `string`
+    /// - Booleans: `bool`
+    ///
+    /// Nested structs and maps are flattened, with keys joined by dots (e.g.
+    /// `address.city`).  `None` and unit values are skipped.
+    ///
+    /// Sequences, tuples and bytes have no tag equivalent, so they cause an
+    /// error.  Tags added before the error are kept.
+    ///
+    /// # Example
+    /// ```
+    /// # use mvt::{Error, GeomEncoder, GeomType, Tile};
+    /// # use serde::Serialize;
+    /// # fn main() -> Result<(), Error> {
+    /// #[derive(Serialize)]
+    /// struct Road {
+    ///     name: String,
+    ///     lanes: u32,
+    ///     speed: Option<f64>,
+    /// }
+    /// let tile = Tile::new(4096);
+    /// let layer = tile.create_layer("roads");
+    /// let geom_data = GeomEncoder::new(GeomType::Point)
+    ///     .point(1.0, 2.0)?
+    ///     .encode()?;
+    /// let mut feature = layer.into_feature(geom_data);
+    /// let road = Road {
+    ///     name: "Main St".to_string(),
+    ///     lanes: 2,
+    ///     speed: None,
+    /// };
+    /// feature.add_tags_from(&road)?;
+    /// # Ok(()) }
+    /// ```
+    pub fn add_tags_from<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(TagSerializer {
+            feature: self,
+            key: None,
+        })
+    }
+}
+
+/// Serializer for one tag value (or nested values)
+struct TagSerializer<'a> {
+    /// Feature to add tags
+    feature: &'a mut Feature,
+
+    /// Tag key (`None` at top level)
+    key: Option<String>,
+}
+
+impl<'a> TagSerializer<'a> {
+    /// Check that a tag key exists (not at top level)
+    fn check_key(&self) -> Result<()> {
+        match self.key {
+            Some(_) => Ok(()),
+            None => Err(Error::Serde("tags must be a struct or map".into())),
+        }
+    }
+
+    /// Split into feature and tag key
+    fn into_parts(self) -> Result<(&'a mut Feature, String)> {
+        self.check_key()?;
+        Ok((self.feature, self.key.unwrap_or_default()))
+    }
+
+    /// Make a nested key
+    fn nested(&self, name: &str) -> String {
+        match &self.key {
+            Some(key) => format!("{key}.{name}"),
+            None => name.to_string(),
+        }
+    }
+
+    /// Make a compound serializer for nested values
+    fn compound(self) -> Compound<'a> {
+        Compound {
+            feature: self.feature,
+            key: self.key,
+            next_key: None,
+        }
+    }
+
+    /// Make a compound serializer nested within an enum variant
+    fn variant(self, variant: &str) -> Compound<'a> {
+        let key = Some(self.nested(variant));
+        Compound {
+            feature: self.feature,
+            key,
+            next_key: None,
+        }
+    }
+}
+
+impl<'a> ser::Serializer for TagSerializer<'a> {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = Impossible<(), Error>;
+    type SerializeTuple = Impossible<(), Error>;
+    type SerializeTupleStruct = Impossible<(), Error>;
+    type SerializeTupleVariant = Impossible<(), Error>;
+    type SerializeMap = Compound<'a>;
+    type SerializeStruct = Compound<'a>;
+    type SerializeStructVariant = Compound<'a>;
+
+    fn serialize_bool(self, v: bool) -> Result<()> {
+        let (feature, key) = self.into_parts()?;
+        feature.add_tag_bool(&key, v);
+        Ok(())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<()> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<()> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<()> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<()> {
+        let (feature, key) = self.into_parts()?;
+        match u64::try_from(v) {
+            Ok(v) => feature.add_tag_uint(&key, v),
+            Err(_) => feature.add_tag_sint(&key, v),
+        }
+        Ok(())
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<()> {
+        self.serialize_u64(u64::from(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<()> {
+        self.serialize_u64(u64::from(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<()> {
+        self.serialize_u64(u64::from(v))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<()> {
+        let (feature, key) = self.into_parts()?;
+        feature.add_tag_uint(&key, v);
+        Ok(())
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<()> {
+        self.serialize_f64(f64::from(v))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<()> {
+        let (feature, key) = self.into_parts()?;
+        feature.add_tag_double(&key, v);
+        Ok(())
+    }
+
+    fn serialize_char(self, v: char) -> Result<()> {
+        self.serialize_str(v.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<()> {
+        let (feature, key) = self.into_parts()?;
+        feature.add_tag_string(&key, v);
+        Ok(())
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<()> {
+        Err(Error::Serde("bytes are not supported".to_string()))
+    }
+
+    fn serialize_none(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<()> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        let key = Some(self.nested(variant));
+        value.serialize(TagSerializer {
+            feature: self.feature,
+            key,
+        })
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Err(Error::Serde("sequences are not supported".to_string()))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Compound<'a>> {
+        Ok(self.compound())
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Compound<'a>> {
+        Ok(self.compound())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Compound<'a>> {
+        Ok(self.variant(variant))
+    }
+}
+
+/// Serializer for nested values (structs and maps)
+struct Compound<'a> {
+    /// Feature to add tags
+    feature: &'a mut Feature,
+
+    /// Key prefix (`None` at top level)
+    key: Option<String>,
+
+    /// Key of next map value
+    next_key: Option<String>,
+}
+
+impl Compound<'_> {
+    /// Serialize a value with a nested key
+    fn nested<T>(&mut self, name: &str, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        let key = Some(match &self.key {
+            Some(key) => format!("{key}.{name}"),
+            None => name.to_string(),
+        });
+        value.serialize(TagSerializer {
+            feature: self.feature,
+            key,
+        })
+    }
+}
+
+impl ser::SerializeMap for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.next_key = Some(key.serialize(KeySerializer)?);
+        Ok(())
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        let key = self
+            .next_key
+            .take()
+            .ok_or_else(|| Error::Serde("map value without key".to_string()))?;
+        self.nested(&key, value)
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeStruct for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.nested(key, value)
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeStructVariant for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.nested(key, value)
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Serializer for map keys
+struct KeySerializer;
+
+impl KeySerializer {
+    /// Make an error for an invalid key
+    fn invalid() -> Error {
+        Error::Serde("map keys must be strings or numbers".to_string())
+    }
+}
+
+impl ser::Serializer for KeySerializer {
+    type Ok = String;
+    type Error = Error;
+    type SerializeSeq = Impossible<String, Error>;
+    type SerializeTuple = Impossible<String, Error>;
+    type SerializeTupleStruct = Impossible<String, Error>;
+    type SerializeTupleVariant = Impossible<String, Error>;
+    type SerializeMap = Impossible<String, Error>;
+    type SerializeStruct = Impossible<String, Error>;
+    type SerializeStructVariant = Impossible<String, Error>;
+
+    fn serialize_bool(self, v: bool) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<String> {
+        Err(Self::invalid())
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<String> {
+        Err(Self::invalid())
+    }
+
+    fn serialize_char(self, v: char) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<String> {
+        Err(Self::invalid())
+    }
+
+    fn serialize_none(self) -> Result<String> {
+        Err(Self::invalid())
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<String>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<String> {
+        Err(Self::invalid())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<String> {
+        Err(Self::invalid())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<String> {
+        Ok(variant.to_string())
+    }
+
+    fn serialize_newtype_struct<T>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<String>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<String>
+    where
+        T: Serialize + ?Sized,
+    {
+        Err(Self::invalid())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Err(Self::invalid())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Err(Self::invalid())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        Err(Self::invalid())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(Self::invalid())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Err(Self::invalid())
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct> {
+        Err(Self::invalid())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(Self::invalid())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::encoder::{GeomEncoder, GeomType};
+    use crate::tile::Tile;
+    use std::collections::BTreeMap;
+
+    #[derive(Serialize)]
+    struct Address {
+        city: &'static str,
+        zip: Option<u32>,
+    }
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "lowercase")]
+    enum Surface {
+        Paved,
+    }
+
+    #[derive(Serialize)]
+    struct Road {
+        name: &'static str,
+        lanes: u8,
+        offset: i32,
+        speed: f32,
+        oneway: bool,
+        surface: Surface,
+        ref_id: Option<u64>,
+        address: Address,
+    }
+
+    #[derive(Serialize)]
+    struct Route {
+        name: &'static str,
+        routes: Vec<&'static str>,
+    }
+
+    fn feature() -> Feature {
+        let tile = Tile::new(4096);
+        let layer = tile.create_layer("roads");
+        let geom_data = GeomEncoder::new(GeomType::Point)
+            .point(1.0, 2.0)
+            .unwrap()
+            .encode()
+            .unwrap();
+        layer.into_feature(geom_data)
+    }
+
+    #[test]
+    fn test_add_tags_from() {
+        let road = Road {
+            name: "Main St",
+            lanes: 2,
+            offset: -3,
+            speed: 35.5,
+            oneway: true,
+            surface: Surface::Paved,
+            ref_id: None,
+            address: Address {
+                city: "Anytown",
+                zip: None,
+            },
+        };
+        let mut feature = feature();
+        feature.add_tags_from(&road).unwrap();
+        let layer = feature.into_layer();
+        let keys = &layer.layer.keys;
+        assert_eq!(
+            keys,
+            &[
+                "name",
+                "lanes",
+                "offset",
+                "speed",
+                "oneway",
+                "surface",
+                "address.city",
+            ]
+        );
+        let values = &layer.layer.values;
+        assert_eq!(values[1].uint_value, Some(2));
+        assert_eq!(values[2].sint_value, Some(-3));
+        assert_eq!(values[3].double_value, Some(35.5));
+        assert_eq!(values[4].bool_value, Some(true));
+        assert_eq!(values[5].string_value.as_deref(), Some("paved"));
+    }
+
+    #[test]
+    fn test_add_tags_from_seq() {
+        let route = Route {
+            name: "US 1",
+            routes: vec!["US 1", "MN 2"],
+        };
+        let mut feature = feature();
+        assert!(feature.add_tags_from(&route).is_err());
+        assert!(feature.add_tags_from(&(1, 2)).is_err());
+    }
+
+    #[test]
+    fn test_add_tags_from_map() {
+        let mut map = BTreeMap::new();
+        map.insert(1, "one");
+        map.insert(2, "two");
+        let mut feature = feature();
+        assert!(feature.add_tags_from(&5).is_err());
+        assert!(feature.add_tags_from(&[1, 2]).is_err());
+        feature.add_tags_from(&map).unwrap();
+        let layer = feature.into_layer();
+        assert_eq!(layer.layer.keys, &["1", "2"]);
+    }
+}
@@ -0,0 +1,213 @@
+// svg.rs
+//
+// Copyright (c) 2019-2026  Minnesota Department of Transportation
+//
+//! Debug rendering of tiles to SVG.
+//!
+use crate::decoder::{Path, decode_paths_version};
+use crate::encoder::GeomType;
+use crate::error::Result;
+use crate::tile::{Tile, feature_geom_type};
+use crate::vector_tile::tile::Layer as VtLayer;
+use std::fmt::Write;
+
+/// Layer colors, used in order
+const COLORS: [&str; 8] = [
+    "#1f77b4", "#d62728", "#2ca02c", "#ff7f0e", "#9467bd", "#8c564b",
+    "#e377c2", "#17becf",
+];
+
+/// Rendered width / height (pixels)
+const SIZE: u32 = 512;
+
+/// Escape text for XML
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Make path data from paths
+fn path_data(paths: &[Path], close: bool) -> String {
+    let mut d = String::new();
+    for path in paths {
+        for (i, (x, y)) in path.iter().enumerate() {
+            let cmd = if i == 0 { 'M' } else { 'L' };
+            let _ = write!(d, "{cmd}{x} {y} ");
+        }
+        if close {
+            d.push_str("Z ");
+        }
+    }
+    d.truncate(d.trim_end().len());
+    d
+}
+
+/// Write one layer as an SVG group
+fn write_layer(
+    svg: &mut String,
+    layer: &VtLayer,
+    color: &str,
+    extent: u32,
+) -> Result<()> {
+    let name = escape(&layer.name);
+    let layer_extent = layer.extent.unwrap_or(4096);
+    let scale = f64::from(extent) / f64::from(layer_extent.max(1));
+    let _ = write!(svg, r#"<g id="{name}" fill="{color}" stroke="{color}""#);
+    if scale != 1.0 {
+        let _ = write!(svg, r#" transform="scale({scale})""#);
+    }
+    svg.push_str(">\n");
+    let _ = writeln!(svg, "<title>{name}</title>");
+    let radius = f64::from(layer_extent) / 256.0;
+    for feature in &layer.features {
+        let Some(geom_tp) = feature_geom_type(feature) else {
+            continue;
+        };
+        let paths =
+            decode_paths_version(geom_tp, layer.version, &feature.geometry)?;
+        let title = match feature.id {
+            Some(id) => format!("<title>{name}: {id}</title>"),
+            None => format!("<title>{name}</title>"),
+        };
+        match geom_tp {
+            GeomType::Point => {
+                let _ = writeln!(svg, "<g>{title}");
+                for (x, y) in paths.iter().flatten() {
+                    let _ = writeln!(
+                        svg,
+                        r#"<circle cx="{x}" cy="{y}" r="{radius}"/>"#
+                    );
+                }
+                svg.push_str("</g>\n");
+            }
+            GeomType::Linestring => {
+                let d = path_data(&paths, false);
+                let _ = writeln!(
+                    svg,
+                    r#"<path d="{d}" fill="none">{title}</path>"#
+                );
+            }
+            GeomType::Polygon => {
+                let d = path_data(&paths, true);
+                let _ = writeln!(
+                    svg,
+                    "<path d=\"{d}\" fill-opacity=\"0.4\" \
+                    fill-rule=\"evenodd\">{title}</path>"
+                );
+            }
+        }
+    }
+    svg.push_str("</g>\n");
+    Ok(())
+}
+
+impl Tile {
+    /// Render the tile to SVG, for debugging.
+    ///
+    /// * `buffer` Buffer around tile extent to outline (tile units).
+    ///
+    /// Each layer is drawn in a different color, in tile coördinates.
+    /// Features have their layer name and ID as a tooltip.  Polygon holes are
+    /// rendered using even-odd fill.
+    ///
+    /// Returns an error if a feature contains invalid geometry.
+    ///
+    /// # Example
+    /// ```
+    /// # use mvt::{Error, GeomEncoder, GeomType, Tile};
+    /// # fn main() -> Result<(), Error> {
+    /// let mut tile = Tile::new(4096);
+    /// let layer = tile.create_layer("points");
+    /// let geom_data = GeomEncoder::new(GeomType::Point)
+    ///     .point(1.0, 2.0)?
+    ///     .encode()?;
+    /// tile.add_layer(layer.into_feature(geom_data).into_layer())?;
+    /// let svg = tile.to_svg(64)?;
+    /// assert!(svg.starts_with("<svg"));
+    /// # Ok(()) }
+    /// ```
+    pub fn to_svg(&self, buffer: u32) -> Result<String> {
+        let extent = self.extent();
+        let (b, size) =
+            (i64::from(buffer), i64::from(extent) + 2 * i64::from(buffer));
+        let mut svg = String::new();
+        let _ = writeln!(
+            svg,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{SIZE}\" \
+            height=\"{SIZE}\" viewBox=\"{} {} {size} {size}\">",
+            -b, -b
+        );
+        svg.push_str(
+            "<style>* { vector-effect: non-scaling-stroke; }</style>\n",
+        );
+        if buffer > 0 {
+            let _ = writeln!(
+                svg,
+                "<rect x=\"{}\" y=\"{}\" width=\"{size}\" \
+                height=\"{size}\" fill=\"none\" stroke=\"#bbb\" \
+                stroke-dasharray=\"4\"/>",
+                -b, -b
+            );
+        }
+        let _ = writeln!(
+            svg,
+            "<rect x=\"0\" y=\"0\" width=\"{extent}\" \
+            height=\"{extent}\" fill=\"none\" stroke=\"#666\"/>"
+        );
+        for (i, layer) in self.all_layers()?.iter().enumerate() {
+            write_layer(&mut svg, layer, COLORS[i % COLORS.len()], extent)?;
+        }
+        svg.push_str("</svg>\n");
+        Ok(svg)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::encoder::GeomEncoder;
+
+    #[test]
+    fn test_svg() {
+        let mut tile = Tile::new(256);
+        let layer = tile.create_layer("a<b");
+        let geom_data = GeomEncoder::new(GeomType::Polygon)
+            .point(0.0, 0.0)
+            .unwrap()
+            .point(10.0, 0.0)
+            .unwrap()
+            .point(10.0, 10.0)
+            .unwrap()
+            .complete()
+            .unwrap()
+            .point(2.0, 2.0)
+            .unwrap()
+            .point(2.0, 4.0)
+            .unwrap()
+            .point(4.0, 4.0)
+            .unwrap()
+            .encode()
+            .unwrap();
+        let mut feature = layer.into_feature(geom_data);
+        feature.set_id(7);
+        tile.add_layer(feature.into_layer()).unwrap();
+        let svg = tile.to_svg(16).unwrap();
+        assert!(svg.contains(r#"viewBox="-16 -16 288 288""#));
+        assert!(svg.contains(r##"<g id="a&lt;b" fill="#1f77b4""##));
+        assert!(svg.contains(r#"d="M0 0 L10 0 L10 10 Z M2 2 L2 4 L4 4 Z""#));
+        assert!(
+            svg.contains(r#"fill-rule="evenodd"><title>a&lt;b: 7</title>"#)
+        );
+        assert!(svg.ends_with("</svg>\n"));
+    }
+}
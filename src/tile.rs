@@ -4,14 +4,23 @@
 //
 //! Tile, Layer and Feature structs.
 //!
-use crate::encoder::{GeomData, GeomType};
+use crate::decoder::{Path, decode_paths_version};
+use crate::encoder::{GeomData, GeomEncoder, GeomType, ParamInt};
 use crate::error::{Error, Result};
+use crate::extension::{
+    LayerExt, WIRE_LEN, encode_tile, find_field, scan_tile, write_field,
+};
+use crate::measure::{FEATURE_MEASURES, LAYER_MEASURE_SCALE};
+#[cfg(feature = "mvt3")]
+use crate::mvt3::{FEATURE_ELEVATIONS, Pools};
 use crate::vector_tile::Tile as VecTile;
 use crate::vector_tile::tile::{
     Feature as VtFeature, GeomType as VtGeomType, Layer as VtLayer, Value,
 };
+use pointy::{BBox, Bounded, Transform};
 use prost::Message;
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
 use std::io::Write;
 
 /// A tile represents a rectangular region of a map.
@@ -45,13 +54,34 @@ use std::io::Write;
 pub struct Tile {
     vec_tile: VecTile,
     extent: u32,
+    mixed_extent: bool,
+    encoded: Vec<u8>,
+    encoded_names: HashSet<String>,
+    extensions: Vec<u8>,
+    layer_ext: HashMap<String, LayerExt>,
+}
+
+/// Layer header, for checking pre-encoded layers without decoding features
+#[derive(Clone, PartialEq, Message)]
+struct LayerHeader {
+    #[prost(string, required, tag = "1")]
+    name: String,
+    #[prost(uint32, optional, tag = "5", default = "4096")]
+    extent: Option<u32>,
+}
+
+/// Tile header, for checking pre-encoded layers without decoding features
+#[derive(Clone, PartialEq, Message)]
+struct TileHeader {
+    #[prost(message, repeated, tag = "3")]
+    layers: Vec<LayerHeader>,
 }
 
 /// Represents a single MVT attribute value of arbitrary MVT-supported type.
 ///
 /// This is a internal-only data structure, used as a building block to track unique values in a
 /// layer in order to speed up MVT tile generation for tiles with many different attribute values.
-#[derive(Eq, Hash, PartialEq)]
+#[derive(Clone, Eq, Hash, PartialEq)]
 enum ValueKey {
     String(String),
     Float(u32),
@@ -94,6 +124,160 @@ impl From<&Value> for ValueKey {
     }
 }
 
+impl ValueKey {
+    /// Calculate a stable hash of the value (64-bit FNV-1a)
+    fn stable_hash(&self) -> u64 {
+        let (tp, bytes) = match self {
+            Self::String(v) => (1, v.as_bytes().to_vec()),
+            Self::Float(v) => (2, v.to_le_bytes().to_vec()),
+            Self::Double(v) => (3, v.to_le_bytes().to_vec()),
+            Self::Int(v) => (4, v.to_le_bytes().to_vec()),
+            Self::Uint(v) => (5, v.to_le_bytes().to_vec()),
+            Self::Sint(v) => (6, v.to_le_bytes().to_vec()),
+            Self::Bool(v) => (7, vec![u8::from(*v)]),
+            Self::Empty => (0, vec![]),
+        };
+        let mut hash = 0xcbf2_9ce4_8422_2325_u64;
+        for b in std::iter::once(tp).chain(bytes) {
+            hash ^= u64::from(b);
+            hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+        hash
+    }
+}
+
+/// Policy for assigning feature IDs in a [Layer].
+///
+/// [Layer]: struct.Layer.html
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub enum IdPolicy {
+    /// IDs are set manually; duplicates are logged as warnings
+    #[default]
+    Manual,
+
+    /// IDs are set manually; duplicates are rejected (see
+    /// [Feature::try_set_id](struct.Feature.html#method.try_set_id))
+    Strict,
+
+    /// IDs are assigned automatically, incrementing from 1 and skipping any
+    /// IDs which have been set manually
+    AutoIncrement,
+
+    /// IDs are assigned from a hash of the value of the tag with the given
+    /// key.  Features without that tag are not assigned an ID.
+    ///
+    /// Hashes are 64-bit, so collisions are very unlikely, but possible.  If
+    /// two different values in a layer hash to the same ID, the feature
+    /// with the later value is not assigned an ID, and a warning is logged.
+    HashTag(String),
+}
+
+/// Order for sorting features in a [Layer].
+///
+/// [Layer]: struct.Layer.html
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum FeatureOrder {
+    /// Sort by value of the tag with the given key.  Booleans sort before
+    /// numbers, which sort before strings; features without the tag are last.
+    /// NaN numbers sort after all other numbers.
+    Tag(String),
+
+    /// Sort by geometry type: points, linestrings, then polygons
+    GeomType,
+
+    /// Sort along a Hilbert curve, using the first point of each feature
+    Hilbert,
+}
+
+/// Key for sorting features by tag value
+enum TagSortKey<'a> {
+    Bool(bool),
+    Number(f64),
+    String(&'a str),
+    Missing,
+}
+
+impl<'a> From<&'a Value> for TagSortKey<'a> {
+    fn from(value: &'a Value) -> Self {
+        if let Some(v) = &value.string_value {
+            Self::String(v)
+        } else if let Some(v) = value.float_value {
+            Self::Number(f64::from(v))
+        } else if let Some(v) = value.double_value {
+            Self::Number(v)
+        } else if let Some(v) = value.int_value.or(value.sint_value) {
+            Self::Number(v as f64)
+        } else if let Some(v) = value.uint_value {
+            Self::Number(v as f64)
+        } else if let Some(v) = value.bool_value {
+            Self::Bool(v)
+        } else {
+            Self::Missing
+        }
+    }
+}
+
+impl TagSortKey<'_> {
+    /// Get rank of variant
+    fn rank(&self) -> u8 {
+        match self {
+            Self::Bool(_) => 0,
+            Self::Number(_) => 1,
+            Self::String(_) => 2,
+            Self::Missing => 3,
+        }
+    }
+}
+
+impl Ord for TagSortKey<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Bool(a), Self::Bool(b)) => a.cmp(b),
+            (Self::Number(a), Self::Number(b)) => a.total_cmp(b),
+            (Self::String(a), Self::String(b)) => a.cmp(b),
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
+}
+
+impl PartialOrd for TagSortKey<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for TagSortKey<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for TagSortKey<'_> {}
+
+/// Calculate distance along a Hilbert curve.
+///
+/// * `n` Size of curve grid (power of two).
+/// * `x` X coördinate (less than `n`).
+/// * `y` Y coördinate (less than `n`).
+fn hilbert_distance(n: u64, mut x: u64, mut y: u64) -> u64 {
+    let mut d = 0;
+    let mut s = n / 2;
+    while s > 0 {
+        let rx = u64::from(x & s > 0);
+        let ry = u64::from(y & s > 0);
+        d += s * s * ((3 * rx) ^ ry);
+        if ry == 0 {
+            if rx == 1 {
+                x = n - 1 - x;
+                y = n - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        s /= 2;
+    }
+    d
+}
+
 /// A layer is a set of related features in a tile.
 ///
 /// # Example
@@ -107,9 +291,17 @@ impl From<&Value> for ValueKey {
 /// // ...
 /// ```
 pub struct Layer {
-    layer: VtLayer,
+    pub(crate) layer: VtLayer,
     key_indices: HashMap<String, usize>,
     value_indices: HashMap<ValueKey, usize>,
+    id_policy: IdPolicy,
+    ids: HashSet<u64>,
+    next_id: u64,
+    hashed_values: HashMap<u64, ValueKey>,
+    pub(crate) ext: LayerExt,
+    pub(crate) measure_scale: f64,
+    #[cfg(feature = "mvt3")]
+    pub(crate) pools: Pools,
 }
 
 /// A Feature contains map geometry with related metadata.
@@ -144,9 +336,25 @@ pub struct Layer {
 /// [Feature.into_layer]: struct.Feature.html#method.into_layer
 pub struct Feature {
     feature: VtFeature,
-    layer: Layer,
+    pub(crate) layer: Layer,
     num_keys: usize,
     num_values: usize,
+    value_ext: Vec<(usize, Vec<u8>)>,
+    #[cfg(feature = "mvt3")]
+    pub(crate) attributes: Vec<u64>,
+}
+
+/// A read-only view of a feature in a tile.
+///
+/// Feature views can be obtained with [Tile.features].
+///
+/// [Tile.features]: struct.Tile.html#method.features
+#[derive(Clone, Copy)]
+pub struct FeatureView<'a> {
+    layer: &'a VtLayer,
+    feature: &'a VtFeature,
+    ext: Option<&'a LayerExt>,
+    index: usize,
 }
 
 impl Tile {
@@ -155,17 +363,131 @@ impl Tile {
     /// * `extent` Height / width of tile bounds.
     pub fn new(extent: u32) -> Self {
         let vec_tile = VecTile::default();
-        Tile { vec_tile, extent }
+        Tile {
+            vec_tile,
+            extent,
+            mixed_extent: false,
+            encoded: Vec::new(),
+            encoded_names: HashSet::new(),
+            extensions: Vec::new(),
+            layer_ext: HashMap::new(),
+        }
+    }
+
+    /// Create a new tile, allowing layers with differing extents.
+    ///
+    /// * `extent` Default height / width of layer bounds.
+    ///
+    /// Layers with other extents can be created with
+    /// [create_layer_with_extent](#method.create_layer_with_extent).
+    pub fn new_mixed_extent(extent: u32) -> Self {
+        let vec_tile = VecTile::default();
+        Tile {
+            vec_tile,
+            extent,
+            mixed_extent: true,
+            encoded: Vec::new(),
+            encoded_names: HashSet::new(),
+            extensions: Vec::new(),
+            layer_ext: HashMap::new(),
+        }
+    }
+
+    /// Decode a tile from bytes.
+    ///
+    /// * `data` Encoded tile data.
+    ///
+    /// The tile extent is taken from the first layer.  If other layers have
+    /// differing extents, the tile allows mixed extents.
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        let vec_tile = VecTile::decode(data)?;
+        let (extensions, layer_exts) = scan_tile(data)?;
+        let layer_ext = vec_tile
+            .layers
+            .iter()
+            .zip(layer_exts)
+            .filter(|(_, ext)| !ext.is_empty())
+            .map(|(layer, ext)| (layer.name.clone(), ext))
+            .collect();
+        let extent = vec_tile
+            .layers
+            .first()
+            .map(|layer| layer.extent.unwrap_or(4096))
+            .unwrap_or(4096);
+        let mixed_extent = vec_tile
+            .layers
+            .iter()
+            .any(|layer| layer.extent.unwrap_or(4096) != extent);
+        Ok(Tile {
+            vec_tile,
+            extent,
+            mixed_extent,
+            encoded: Vec::new(),
+            encoded_names: HashSet::new(),
+            extensions,
+            layer_ext,
+        })
     }
 
     /// Get extent, or height / width of tile bounds.
+    ///
+    /// For tiles with mixed extents, this is the default layer extent.
     pub fn extent(&self) -> u32 {
         self.extent
     }
 
-    /// Get the number of layers.
+    /// Check if layers with differing extents are allowed.
+    pub fn is_mixed_extent(&self) -> bool {
+        self.mixed_extent
+    }
+
+    /// Get the number of layers (including pre-encoded layers).
     pub fn num_layers(&self) -> usize {
-        self.vec_tile.layers.len()
+        self.vec_tile.layers.len() + self.encoded_names.len()
+    }
+
+    /// Get views of all features in a layer.
+    ///
+    /// * `name` Layer name.
+    ///
+    /// Pre-encoded layers are not included.
+    ///
+    /// # Example
+    /// ```
+    /// # use mvt::{Error, Tile};
+    /// # fn main() -> Result<(), Error> {
+    /// # let data = Tile::new(4096).to_bytes()?;
+    /// let tile = Tile::from_bytes(&data)?;
+    /// for feature in tile.features("roads") {
+    ///     println!("{:?} {:?}", feature.id(), feature.geom_type());
+    /// }
+    /// # Ok(()) }
+    /// ```
+    pub fn features(
+        &self,
+        name: &str,
+    ) -> impl Iterator<Item = FeatureView<'_>> {
+        self.vec_tile
+            .layers
+            .iter()
+            .filter(move |layer| layer.name == name)
+            .flat_map(|layer| {
+                let ext = self.layer_ext.get(&layer.name);
+                layer.features.iter().enumerate().map(
+                    move |(index, feature)| FeatureView {
+                        layer,
+                        feature,
+                        ext,
+                        index,
+                    },
+                )
+            })
+    }
+
+    /// Check if a layer name exists
+    fn has_layer_name(&self, name: &str) -> bool {
+        self.vec_tile.layers.iter().any(|n| n.name == name)
+            || self.encoded_names.contains(name)
     }
 
     /// Create a new layer.
@@ -175,47 +497,401 @@ impl Tile {
         Layer::new(name, self.extent)
     }
 
+    /// Create a new layer with a specific extent.
+    ///
+    /// * `name` Layer name.
+    /// * `extent` Width / height of layer bounds.
+    ///
+    /// The layer can only be added to a tile with mixed extents, unless
+    /// `extent` matches the tile extent.
+    pub fn create_layer_with_extent(&self, name: &str, extent: u32) -> Layer {
+        Layer::new(name, extent)
+    }
+
+    /// Create a new version 1 layer, for legacy clients.
+    ///
+    /// * `name` Layer name.
+    ///
+    /// Version 1 has no polygon winding order requirements, and no
+    /// ClosePath semantics.  Use
+    /// [GeomEncoder::version](struct.GeomEncoder.html#method.version) to
+    /// encode geometry with matching rules.
+    pub fn create_layer_v1(&self, name: &str) -> Layer {
+        let mut layer = Layer::new(name, self.extent);
+        layer.layer.version = 1;
+        layer
+    }
+
     /// Add a layer.
     ///
     /// * `layer` The layer.
     ///
     /// Returns an error if:
     /// * a layer with the same name already exists
-    /// * the layer extent does not match the tile extent
+    /// * the layer extent does not match the tile extent (unless the tile
+    ///   allows mixed extents)
     pub fn add_layer(&mut self, layer: Layer) -> Result<()> {
-        if layer.layer.extent != Some(self.extent) {
+        if !self.mixed_extent && layer.layer.extent != Some(self.extent) {
             return Err(Error::WrongExtent());
         }
-        if self
-            .vec_tile
-            .layers
-            .iter()
-            .any(|n| n.name == layer.layer.name)
-        {
+        if self.has_layer_name(&layer.layer.name) {
             Err(Error::DuplicateName())
         } else {
+            let layer = layer.encode_measure_scale();
+            #[cfg(feature = "mvt3")]
+            let layer = layer.encode_pools();
+            if !layer.ext.is_empty() {
+                self.layer_ext.insert(layer.layer.name.clone(), layer.ext);
+            }
             self.vec_tile.layers.push(layer.layer);
             Ok(())
         }
     }
 
+    /// Add an extension field.
+    ///
+    /// * `number` Field number (16 to 8191).
+    /// * `data` Field data (length-delimited).
+    ///
+    /// Extension fields are preserved when decoding and encoding tiles.
+    pub fn add_extension(&mut self, number: u32, data: &[u8]) -> Result<()> {
+        if !(16..=8191).contains(&number) {
+            return Err(Error::InvalidExtension());
+        }
+        write_field(&mut self.extensions, number, data);
+        Ok(())
+    }
+
+    /// Get encoded extension (and other unknown) fields.
+    pub fn extensions(&self) -> &[u8] {
+        &self.extensions
+    }
+
+    /// Get encoded extension (and other unknown) fields of a layer.
+    ///
+    /// * `name` Layer name.
+    pub fn layer_extensions(&self, name: &str) -> &[u8] {
+        self.layer_ext
+            .get(name)
+            .map(|ext| &ext.fields[..])
+            .unwrap_or_default()
+    }
+
+    /// Add a transformed layer, with its unknown fields.
+    ///
+    /// * `layer` Transformed layer.
+    /// * `indices` Source index of each feature in the transformed layer.
+    /// * `src` Source tile.
+    ///
+    /// Returns an error if a feature has per-vertex fields (measures or
+    /// elevations), since they are not re-interpolated.
+    fn add_transformed_layer(
+        &mut self,
+        layer: VtLayer,
+        indices: &[usize],
+        src: &Tile,
+    ) -> Result<()> {
+        if let Some(ext) = src.layer_ext.get(&layer.name) {
+            let mut features = HashMap::new();
+            for (index, i) in indices.iter().enumerate() {
+                let Some(fields) = ext.features.get(i) else {
+                    continue;
+                };
+                #[cfg(feature = "mvt3")]
+                if layer.version >= 3
+                    && find_field(fields, FEATURE_ELEVATIONS, WIRE_LEN)
+                        .is_some()
+                {
+                    return Err(Error::FeatureExtension());
+                }
+                if find_field(fields, FEATURE_MEASURES, WIRE_LEN).is_some() {
+                    return Err(Error::FeatureExtension());
+                }
+                features.insert(index, fields.clone());
+            }
+            let ext = LayerExt {
+                fields: ext.fields.clone(),
+                features,
+                values: ext.values.clone(),
+            };
+            if !ext.is_empty() {
+                self.layer_ext.insert(layer.name.clone(), ext);
+            }
+        }
+        self.vec_tile.layers.push(layer);
+        Ok(())
+    }
+
+    /// Add pre-encoded layers.
+    ///
+    /// * `data` Encoded tile data, such as the output of PostGIS `ST_AsMVT`.
+    ///
+    /// Only layer names and extents are checked; features are not decoded.
+    /// The data is written as-is, after all other layers.
+    ///
+    /// Returns an error if:
+    /// * the data is not a valid tile
+    /// * a layer with the same name already exists
+    /// * a layer extent does not match the tile extent (unless the tile
+    ///   allows mixed extents)
+    pub fn add_encoded_layer(&mut self, data: &[u8]) -> Result<()> {
+        let header = TileHeader::decode(data)?;
+        let mut names = HashSet::new();
+        for layer in &header.layers {
+            if !self.mixed_extent && layer.extent() != self.extent {
+                return Err(Error::WrongExtent());
+            }
+            if self.has_layer_name(&layer.name) || !names.insert(&layer.name) {
+                return Err(Error::DuplicateName());
+            }
+        }
+        self.encoded.extend_from_slice(data);
+        self.encoded_names
+            .extend(header.layers.into_iter().map(|layer| layer.name));
+        Ok(())
+    }
+
+    /// Get all layers, decoding any pre-encoded layers
+    pub(crate) fn all_layers(&self) -> Result<Vec<VtLayer>> {
+        let mut layers = self.vec_tile.layers.clone();
+        layers.extend(VecTile::decode(&self.encoded[..])?.layers);
+        Ok(layers)
+    }
+
+    /// Derive a child tile by cutting it out of this tile (overzoom).
+    ///
+    /// * `path` Child quadrant path, one entry per zoom level.  Each
+    ///   quadrant is 0 (upper left), 1 (upper right), 2 (lower left) or 3
+    ///   (lower right).
+    /// * `buffer` Buffer around child tile bounds (in tile units).
+    ///
+    /// Geometry is scaled by 2^n (where n is the path length) and clipped
+    /// to the child tile bounds plus buffer.  Features which are entirely
+    /// clipped are omitted; tags and extension fields are preserved.
+    ///
+    /// Returns an error if:
+    /// * a quadrant is not in the range 0-3
+    /// * a feature contains invalid geometry
+    /// * a feature has measures or elevations, which are not re-interpolated
+    pub fn overzoom(&self, path: &[u8], buffer: u32) -> Result<Tile> {
+        let (mut x, mut y) = (0.0, 0.0);
+        let mut scale = 1.0;
+        for quadrant in path {
+            if *quadrant > 3 {
+                return Err(Error::InvalidQuadrant());
+            }
+            scale *= 2.0;
+            x = x * 2.0 + f64::from(quadrant & 1);
+            y = y * 2.0 + f64::from(quadrant >> 1);
+        }
+        let mut tile = Tile {
+            vec_tile: VecTile::default(),
+            extent: self.extent,
+            mixed_extent: self.mixed_extent,
+            encoded: Vec::new(),
+            encoded_names: HashSet::new(),
+            extensions: self.extensions.clone(),
+            layer_ext: HashMap::new(),
+        };
+        for layer in &self.all_layers()? {
+            let extent = f64::from(layer.extent.unwrap_or(4096));
+            let span = extent / scale;
+            let (x0, y0) = (x * span, y * span);
+            let buf = f64::from(buffer) / scale;
+            let bbox = BBox::new([
+                (x0 - buf, y0 - buf),
+                (x0 + span + buf, y0 + span + buf),
+            ]);
+            let transform =
+                Transform::with_translate(-x0, -y0).scale(scale, scale);
+            let (layer, indices) =
+                transform_layer(layer, transform, Some(bbox))?;
+            tile.add_transformed_layer(layer, &indices, self)?;
+        }
+        Ok(tile)
+    }
+
+    /// Change the extent of all layers, re-quantizing geometry.
+    ///
+    /// * `extent` New height / width of tile bounds.
+    ///
+    /// Geometry is scaled to the new extent, and simplified to remove any
+    /// redundant or collinear points.  Features which collapse entirely are
+    /// omitted; tags and extension fields are preserved.
+    ///
+    /// Returns an error if a feature contains invalid geometry, or has
+    /// measures or elevations (which are not re-interpolated).
+    pub fn with_extent(&self, extent: u32) -> Result<Tile> {
+        let mut tile = Tile::new(extent);
+        tile.extensions = self.extensions.clone();
+        for layer in &self.all_layers()? {
+            let scale =
+                f64::from(extent) / f64::from(layer.extent.unwrap_or(4096));
+            let transform = Transform::with_scale(scale, scale);
+            let (mut layer, indices) = transform_layer(layer, transform, None)?;
+            layer.extent = Some(extent);
+            tile.add_transformed_layer(layer, &indices, self)?;
+        }
+        Ok(tile)
+    }
+
     /// Write the tile.
     ///
     /// * `out` Writer to output the tile.
     pub fn write_to(&self, out: &mut dyn Write) -> Result<()> {
-        out.write_all(&self.vec_tile.encode_to_vec())?;
+        out.write_all(&self.to_bytes()?)?;
         Ok(())
     }
 
     /// Encode the tile and return the bytes.
     pub fn to_bytes(&self) -> Result<Vec<u8>> {
-        Ok(self.vec_tile.encode_to_vec())
+        let mut data =
+            if self.extensions.is_empty() && self.layer_ext.is_empty() {
+                self.vec_tile.encode_to_vec()
+            } else {
+                encode_tile(&self.vec_tile, &self.extensions, &self.layer_ext)
+            };
+        data.extend_from_slice(&self.encoded);
+        Ok(data)
     }
 
     /// Compute the encoded size in bytes.
     pub fn compute_size(&self) -> usize {
-        self.vec_tile.encoded_len()
+        if self.extensions.is_empty() && self.layer_ext.is_empty() {
+            self.vec_tile.encoded_len() + self.encoded.len()
+        } else {
+            encode_tile(&self.vec_tile, &self.extensions, &self.layer_ext).len()
+                + self.encoded.len()
+        }
+    }
+}
+
+/// Get the geometry type of a feature
+pub(crate) fn feature_geom_type(feature: &VtFeature) -> Option<GeomType> {
+    match feature.r#type() {
+        VtGeomType::Point => Some(GeomType::Point),
+        VtGeomType::Linestring => Some(GeomType::Linestring),
+        VtGeomType::Polygon => Some(GeomType::Polygon),
+        VtGeomType::Unknown => None,
+    }
+}
+
+/// Encode paths of tile coördinates.
+///
+/// * `enc` Geometry encoder.
+/// * `paths` Paths to encode.
+fn encode_paths(
+    mut enc: GeomEncoder<f64>,
+    paths: Vec<Path>,
+) -> Result<GeomData> {
+    for path in paths {
+        for (x, y) in path {
+            enc.add_point(f64::from(x), f64::from(y))?;
+        }
+        enc.complete_geom()?;
     }
+    enc.encode()
+}
+
+/// Join linestring paths which share endpoints.
+///
+/// Paths are only joined end-to-start, so their direction is preserved.
+fn join_paths(paths: Vec<Path>) -> Vec<Path> {
+    let mut starts: HashMap<(i32, i32), Vec<usize>> = HashMap::new();
+    let mut ends: HashMap<(i32, i32), usize> = HashMap::new();
+    for (i, path) in paths.iter().enumerate() {
+        if let (Some(first), Some(last)) = (path.first(), path.last()) {
+            starts.entry(*first).or_default().push(i);
+            *ends.entry(*last).or_default() += 1;
+        }
+    }
+    let mut used = vec![false; paths.len()];
+    let mut joined = Vec::with_capacity(paths.len());
+    // Start with paths which no other path leads into, then any remaining
+    // (cyclic) paths
+    let heads: Vec<usize> = (0..paths.len())
+        .filter(|i| {
+            paths[*i]
+                .first()
+                .is_none_or(|first| !ends.contains_key(first))
+        })
+        .chain(0..paths.len())
+        .collect();
+    for i in heads {
+        if used[i] {
+            continue;
+        }
+        used[i] = true;
+        let mut path = paths[i].clone();
+        while let Some(last) = path.last() {
+            let next = starts
+                .get(last)
+                .and_then(|idx| idx.iter().find(|j| !used[**j]).copied());
+            let Some(j) = next else {
+                break;
+            };
+            used[j] = true;
+            path.extend_from_slice(&paths[j][1..]);
+        }
+        joined.push(path);
+    }
+    joined
+}
+
+/// Transform the geometry of all features in a layer.
+///
+/// * `layer` Layer to transform.
+/// * `transform` Transform to apply to tile coördinates.
+/// * `bbox` Bounding box for clipping (before transform).
+///
+/// Features with unknown geometry type, or which are entirely clipped, are
+/// omitted.  Returns the layer, with the source index of each kept feature.
+fn transform_layer(
+    layer: &VtLayer,
+    transform: Transform<f64>,
+    bbox: Option<BBox<f64>>,
+) -> Result<(VtLayer, Vec<usize>)> {
+    let mut features = Vec::with_capacity(layer.features.len());
+    let mut indices = Vec::with_capacity(layer.features.len());
+    for (i, feature) in layer.features.iter().enumerate() {
+        let Some(geom_tp) = feature_geom_type(feature) else {
+            continue;
+        };
+        let paths =
+            decode_paths_version(geom_tp, layer.version, &feature.geometry)?;
+        let mut enc = GeomEncoder::new(geom_tp)
+            .layer_version(layer.version)
+            .transform(transform);
+        if let Some(bbox) = bbox {
+            let pts = paths
+                .iter()
+                .flatten()
+                .map(|(x, y)| (f64::from(*x), f64::from(*y)));
+            if !BBox::new(pts).bounded_by(bbox) {
+                continue;
+            }
+            enc = enc.bbox(bbox);
+        }
+        let geom_data = encode_paths(enc, paths)?;
+        if !geom_data.is_empty() {
+            features.push(VtFeature {
+                id: feature.id,
+                tags: feature.tags.clone(),
+                r#type: feature.r#type,
+                geometry: geom_data.into_vec(),
+            });
+            indices.push(i);
+        }
+    }
+    let layer = VtLayer {
+        version: layer.version,
+        name: layer.name.clone(),
+        features,
+        keys: layer.keys.clone(),
+        values: layer.values.clone(),
+        extent: layer.extent,
+    };
+    Ok((layer, indices))
 }
 
 impl Default for Layer {
@@ -225,6 +901,14 @@ impl Default for Layer {
             layer,
             key_indices: HashMap::new(),
             value_indices: HashMap::new(),
+            id_policy: IdPolicy::default(),
+            ids: HashSet::new(),
+            next_id: 1,
+            hashed_values: HashMap::new(),
+            ext: LayerExt::default(),
+            measure_scale: 1.0,
+            #[cfg(feature = "mvt3")]
+            pools: Pools::default(),
         }
     }
 }
@@ -243,11 +927,40 @@ impl Layer {
         };
         Layer {
             layer,
-            key_indices: HashMap::new(),
-            value_indices: HashMap::new(),
+            ..Default::default()
         }
     }
 
+    /// Set the feature ID policy.
+    ///
+    /// * `policy` ID policy for features added after this call.
+    pub fn id_policy(mut self, policy: IdPolicy) -> Self {
+        self.id_policy = policy;
+        self
+    }
+
+    /// Add an extension field.
+    ///
+    /// * `number` Field number (16 or higher).
+    /// * `data` Field data (length-delimited).
+    ///
+    /// Field 8192 is reserved for the
+    /// [measure scale](#method.measure_scale), and 19000 to 19999 are
+    /// reserved by protobuf.
+    pub fn add_extension(&mut self, number: u32, data: &[u8]) -> Result<()> {
+        check_extension(number, 16)?;
+        if number == LAYER_MEASURE_SCALE {
+            return Err(Error::InvalidExtension());
+        }
+        write_field(&mut self.ext.fields, number, data);
+        Ok(())
+    }
+
+    /// Get encoded extension fields.
+    pub fn extensions(&self) -> &[u8] {
+        &self.ext.fields
+    }
+
     /// Get the layer name.
     pub fn name(&self) -> Option<&str> {
         Some(&self.layer.name)
@@ -264,6 +977,15 @@ impl Layer {
     pub fn into_feature(self, geom_data: GeomData) -> Feature {
         let num_keys = self.layer.keys.len();
         let num_values = self.layer.values.len();
+        #[allow(unused_mut)]
+        let mut ext = self.encode_measures(&geom_data);
+        #[cfg(feature = "mvt3")]
+        ext.extend(self.encode_elevations(&geom_data));
+        let mut layer = self;
+        if !ext.is_empty() {
+            let index = layer.layer.features.len();
+            layer.ext.features.insert(index, ext);
+        }
         let feature = VtFeature {
             r#type: Some(match geom_data.geom_type() {
                 GeomType::Point => VtGeomType::Point as i32,
@@ -275,15 +997,18 @@ impl Layer {
         };
         Feature {
             feature,
-            layer: self,
+            layer,
             num_keys,
             num_values,
+            value_ext: Vec::new(),
+            #[cfg(feature = "mvt3")]
+            attributes: Vec::new(),
         }
     }
 
     /// Get position of a key in the layer keys.  If the key is not found, it
     /// is added as the last key.
-    fn key_pos(&mut self, key: &str) -> usize {
+    pub(crate) fn key_pos(&mut self, key: &str) -> usize {
         if let Some(&index) = self.key_indices.get(key) {
             return index;
         }
@@ -294,6 +1019,43 @@ impl Layer {
         index
     }
 
+    /// Get the ID to assign to a feature, according to the ID policy.
+    fn assign_id(&mut self, feature: &VtFeature) -> Option<u64> {
+        match &self.id_policy {
+            IdPolicy::Manual | IdPolicy::Strict => None,
+            IdPolicy::AutoIncrement => {
+                while self.ids.contains(&self.next_id) {
+                    self.next_id += 1;
+                }
+                Some(self.next_id)
+            }
+            IdPolicy::HashTag(key) => {
+                let kidx = *self.key_indices.get(key)? as u32;
+                let vidx = feature
+                    .tags
+                    .chunks_exact(2)
+                    .find(|tag| tag[0] == kidx)
+                    .map(|tag| tag[1] as usize)?;
+                let value = ValueKey::from(self.layer.values.get(vidx)?);
+                let id = value.stable_hash();
+                match self.hashed_values.get(&id) {
+                    Some(other) if *other != value => {
+                        log::warn!(
+                            "Feature ID hash collision ({id}) in layer {:?}",
+                            self.layer.name
+                        );
+                        None
+                    }
+                    Some(_) => Some(id),
+                    None => {
+                        self.hashed_values.insert(id, value);
+                        Some(id)
+                    }
+                }
+            }
+        }
+    }
+
     /// Get position of a value in the layer values.  If the value is not found,
     /// it is added as the last value.
     fn val_pos(&mut self, value: Value) -> usize {
@@ -306,17 +1068,371 @@ impl Layer {
         self.value_indices.insert(value_key, index);
         index
     }
+
+    /// Sort the features in the layer.
+    ///
+    /// * `order` Sort order.
+    ///
+    /// The sort is stable, so features which compare equal keep their
+    /// relative order.  Feature extension fields (such as measures) are kept
+    /// with their features.
+    pub fn sort_features(&mut self, order: FeatureOrder) {
+        let layer = &self.layer;
+        let features = &layer.features;
+        let mut indices: Vec<usize> = (0..features.len()).collect();
+        match order {
+            FeatureOrder::Tag(key) => {
+                let kidx = self.key_indices.get(&key).map(|k| *k as u32);
+                let values = &layer.values;
+                let sort_key = |i: usize| {
+                    kidx.and_then(|kidx| {
+                        features[i]
+                            .tags
+                            .chunks_exact(2)
+                            .find(|tag| tag[0] == kidx)
+                            .and_then(|tag| values.get(tag[1] as usize))
+                    })
+                    .map(TagSortKey::from)
+                    .unwrap_or(TagSortKey::Missing)
+                };
+                indices.sort_by(|a, b| sort_key(*a).cmp(&sort_key(*b)));
+            }
+            FeatureOrder::GeomType => {
+                indices.sort_by_key(|i| features[*i].r#type());
+            }
+            FeatureOrder::Hilbert => {
+                let extent = layer.extent.unwrap_or(4096);
+                let n = u64::from(extent.next_power_of_two());
+                let max = i64::from(extent.max(1) - 1);
+                indices.sort_by_cached_key(|i| {
+                    let f = &features[*i];
+                    if f.geometry.len() < 3 {
+                        return u64::MAX;
+                    }
+                    let x = i64::from(ParamInt::decode(f.geometry[1]).value);
+                    let y = i64::from(ParamInt::decode(f.geometry[2]).value);
+                    let x = x.clamp(0, max) as u64;
+                    let y = y.clamp(0, max) as u64;
+                    hilbert_distance(n, x, y)
+                });
+            }
+        }
+        self.reorder_features(&indices);
+    }
+
+    /// Reorder features, keeping any extension fields with their features.
+    ///
+    /// * `indices` Previous index of each feature, in the new order.
+    fn reorder_features(&mut self, indices: &[usize]) {
+        let mut features: Vec<Option<VtFeature>> =
+            std::mem::take(&mut self.layer.features)
+                .into_iter()
+                .map(Some)
+                .collect();
+        let mut ext = std::mem::take(&mut self.ext.features);
+        for i in indices {
+            if let Some(feature) = features[*i].take() {
+                if let Some(fields) = ext.remove(i) {
+                    let index = self.layer.features.len();
+                    self.ext.features.insert(index, fields);
+                }
+                self.layer.features.push(feature);
+            }
+        }
+    }
+
+    /// Merge features which have identical tags and geometry type.
+    ///
+    /// * `join_lines` Join linestrings which share endpoints.
+    ///
+    /// Each group of matching features is combined into a single feature
+    /// containing multi-geometry, in place of the first feature of the
+    /// group.  The merged feature keeps its ID only if all features in the
+    /// group have the same ID.
+    ///
+    /// Returns an error if a feature contains invalid geometry, or if any
+    /// features to be merged (or joined) have extension fields, such as
+    /// measures.  On error, the layer is unchanged.
+    pub fn merge_features(&mut self, join_lines: bool) -> Result<()> {
+        let features = &self.layer.features;
+        let mut groups: Vec<Vec<usize>> = Vec::new();
+        let mut group_indices: HashMap<(i32, Vec<u32>), usize> = HashMap::new();
+        for (i, feature) in features.iter().enumerate() {
+            let mut tags: Vec<[u32; 2]> = feature
+                .tags
+                .chunks_exact(2)
+                .map(|tag| [tag[0], tag[1]])
+                .collect();
+            tags.sort_unstable();
+            let key = (feature.r#type.unwrap_or_default(), tags.concat());
+            match group_indices.get(&key) {
+                Some(g) => groups[*g].push(i),
+                None => {
+                    group_indices.insert(key, groups.len());
+                    groups.push(vec![i]);
+                }
+            }
+        }
+        // Geometry type of groups which will be re-encoded
+        let merge_type = |group: &[usize]| {
+            let geom_tp = feature_geom_type(&features[group[0]]);
+            let join = join_lines && geom_tp == Some(GeomType::Linestring);
+            geom_tp.filter(|_| group.len() > 1 || join)
+        };
+        let ext = &self.ext.features;
+        if groups.iter().any(|group| {
+            merge_type(group).is_some()
+                && group.iter().any(|i| ext.contains_key(i))
+        }) {
+            return Err(Error::FeatureExtension());
+        }
+        // Encode merged geometry before changing anything
+        let version = self.layer.version;
+        let mut merged = Vec::with_capacity(groups.len());
+        for group in &groups {
+            let Some(geom_tp) = merge_type(group) else {
+                merged.push(None);
+                continue;
+            };
+            let mut paths = Vec::new();
+            for i in group {
+                paths.extend(decode_paths_version(
+                    geom_tp,
+                    version,
+                    &features[*i].geometry,
+                )?);
+            }
+            if join_lines && geom_tp == GeomType::Linestring {
+                paths = join_paths(paths);
+            }
+            let enc = GeomEncoder::new(geom_tp).layer_version(version);
+            let geom_data = encode_paths(enc, paths)?;
+            let id = features[group[0]].id;
+            let id = group.iter().all(|i| features[*i].id == id).then_some(id);
+            merged.push(Some((geom_data.into_vec(), id.flatten())));
+        }
+        let mut indices = Vec::with_capacity(features.len());
+        for (group, merged) in groups.iter().zip(&mut merged) {
+            match merged {
+                Some((geometry, id)) => {
+                    let feature = &mut self.layer.features[group[0]];
+                    feature.geometry = std::mem::take(geometry);
+                    feature.id = *id;
+                    indices.push(group[0]);
+                }
+                None => indices.extend(group),
+            }
+        }
+        self.reorder_features(&indices);
+        self.ids = self.layer.features.iter().filter_map(|f| f.id).collect();
+        Ok(())
+    }
+
+    /// Optimize the layer by sorting keys and values by frequency.
+    ///
+    /// Indices less than 128 are encoded in one byte, so the most frequently
+    /// used keys and values are moved to the lowest indices.  Unused keys and
+    /// values are removed.  Feature tags are updated to match.
+    ///
+    /// Returns the number of bytes saved in the encoded layer.  Layers with
+    /// inline attributes (which refer to keys) are not changed, and return 0.
+    pub fn optimize(&mut self) -> usize {
+        #[cfg(feature = "mvt3")]
+        if self.has_attributes() {
+            return 0;
+        }
+        let before = self.layer.encoded_len();
+        let mut key_counts = vec![0; self.layer.keys.len()];
+        let mut val_counts = vec![0; self.layer.values.len()];
+        for feature in &self.layer.features {
+            for tag in feature.tags.chunks_exact(2) {
+                if let Some(c) = key_counts.get_mut(tag[0] as usize) {
+                    *c += 1;
+                }
+                if let Some(c) = val_counts.get_mut(tag[1] as usize) {
+                    *c += 1;
+                }
+            }
+        }
+        let key_map = frequency_map(&key_counts);
+        let val_map = frequency_map(&val_counts);
+        for feature in &mut self.layer.features {
+            for tag in feature.tags.chunks_exact_mut(2) {
+                if let Some(Some(k)) = key_map.get(tag[0] as usize) {
+                    tag[0] = *k as u32;
+                }
+                if let Some(Some(v)) = val_map.get(tag[1] as usize) {
+                    tag[1] = *v as u32;
+                }
+            }
+        }
+        self.layer.keys = remap(std::mem::take(&mut self.layer.keys), &key_map);
+        self.layer.values =
+            remap(std::mem::take(&mut self.layer.values), &val_map);
+        self.ext.values = std::mem::take(&mut self.ext.values)
+            .into_iter()
+            .filter_map(|(i, ext)| Some((val_map.get(i).copied()??, ext)))
+            .collect();
+        self.key_indices = self
+            .layer
+            .keys
+            .iter()
+            .enumerate()
+            .map(|(i, key)| (key.clone(), i))
+            .collect();
+        self.value_indices = self
+            .layer
+            .values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| (ValueKey::from(value), i))
+            .collect();
+        before.saturating_sub(self.layer.encoded_len())
+    }
+}
+
+/// Check an extension field number.
+///
+/// * `number` Field number.
+/// * `first` First field number of the extension range.
+///
+/// Fields 19000 to 19999 are reserved by protobuf.
+fn check_extension(number: u32, first: u32) -> Result<()> {
+    if (first..=0x1FFF_FFFF).contains(&number)
+        && !(19000..20000).contains(&number)
+    {
+        Ok(())
+    } else {
+        Err(Error::InvalidExtension())
+    }
+}
+
+/// Make a map of new indices, sorted by descending frequency.
+///
+/// Indices with a count of zero are mapped to `None`.
+fn frequency_map(counts: &[usize]) -> Vec<Option<usize>> {
+    let mut order: Vec<usize> = (0..counts.len()).collect();
+    order.sort_by_key(|i| std::cmp::Reverse(counts[*i]));
+    let mut map = vec![None; counts.len()];
+    for (new, old) in order.into_iter().enumerate() {
+        if counts[old] > 0 {
+            map[old] = Some(new);
+        }
+    }
+    map
+}
+
+/// Remap items to new indices, dropping unmapped items.
+fn remap<T>(items: Vec<T>, map: &[Option<usize>]) -> Vec<T> {
+    let mut slots: Vec<Option<T>> = items.iter().map(|_| None).collect();
+    for (old, item) in items.into_iter().enumerate() {
+        if let Some(Some(new)) = map.get(old) {
+            slots[*new] = Some(item);
+        }
+    }
+    slots.into_iter().flatten().collect()
+}
+
+impl<'a> FeatureView<'a> {
+    /// Get the feature ID.
+    pub fn id(&self) -> Option<u64> {
+        self.feature.id
+    }
+
+    /// Get the geometry type.
+    ///
+    /// Returns `None` for unknown geometry.
+    pub fn geom_type(&self) -> Option<GeomType> {
+        feature_geom_type(self.feature)
+    }
+
+    /// Get the number of (valid) tags.
+    pub fn num_tags(&self) -> usize {
+        self.tags().count()
+    }
+
+    /// Get tag keys and values.
+    ///
+    /// Tags with invalid key or value indices are skipped.
+    pub(crate) fn tags(
+        &self,
+    ) -> impl Iterator<Item = (&'a str, &'a Value)> + use<'a> {
+        let layer = self.layer;
+        let feature = self.feature;
+        feature.tags.chunks_exact(2).filter_map(move |kv| {
+            let key = layer.keys.get(kv[0] as usize)?;
+            let val = layer.values.get(kv[1] as usize)?;
+            Some((key.as_str(), val))
+        })
+    }
+
+    /// Get encoded extension (and other unknown) fields.
+    pub fn extensions(&self) -> &'a [u8] {
+        self.ext
+            .and_then(|ext| ext.features.get(&self.index))
+            .map(|fields| &fields[..])
+            .unwrap_or_default()
+    }
+
+    /// Get encoded extension (and other unknown) fields of a tag value.
+    ///
+    /// * `key` Tag key.
+    pub fn tag_extensions(&self, key: &str) -> &'a [u8] {
+        let layer = self.layer;
+        self.feature
+            .tags
+            .chunks_exact(2)
+            .rfind(|kv| {
+                layer.keys.get(kv[0] as usize).is_some_and(|k| k == key)
+            })
+            .and_then(|kv| self.ext?.values.get(&(kv[1] as usize)))
+            .map(|fields| &fields[..])
+            .unwrap_or_default()
+    }
+
+    /// Get encoded extension fields of the layer
+    pub(crate) fn layer_ext_fields(&self) -> &'a [u8] {
+        self.ext.map(|ext| &ext.fields[..]).unwrap_or_default()
+    }
 }
 
 impl Feature {
     /// Complete the feature, returning ownership of the layer.
+    ///
+    /// If the feature has no ID, one may be assigned by the layer's
+    /// [IdPolicy](enum.IdPolicy.html).
     pub fn into_layer(mut self) -> Layer {
+        if self.feature.id.is_none() {
+            self.feature.id = self.layer.assign_id(&self.feature);
+            if let Some(id) = self.feature.id
+                && !self.layer.ids.insert(id)
+            {
+                log::warn!(
+                    "Duplicate feature ID ({id}) in layer {:?}",
+                    self.layer.layer.name
+                );
+            }
+        } else if let Some(id) = self.feature.id {
+            self.layer.ids.insert(id);
+        }
+        for (index, fields) in std::mem::take(&mut self.value_ext) {
+            self.layer
+                .ext
+                .values
+                .entry(index)
+                .or_default()
+                .extend(fields);
+        }
+        #[cfg(feature = "mvt3")]
+        self.encode_attributes();
         self.layer.layer.features.push(self.feature);
         self.layer
     }
 
     /// Get the layer, abandoning the feature.
     pub fn layer(mut self) -> Layer {
+        let index = self.layer.layer.features.len();
+        self.layer.ext.features.remove(&index);
         // Reset key/value lengths
         self.layer.layer.keys.truncate(self.num_keys);
         self.layer.layer.values.truncate(self.num_values);
@@ -330,14 +1446,36 @@ impl Feature {
     }
 
     /// Set the feature ID.
+    ///
+    /// * `id` Feature ID.
+    ///
+    /// Duplicate IDs are logged as warnings.  If the layer
+    /// [IdPolicy](enum.IdPolicy.html) is `Strict`, a duplicate ID is not set;
+    /// use [try_set_id](#method.try_set_id) to handle that as an error.
     pub fn set_id(&mut self, id: u64) {
-        let layer = &self.layer.layer;
-        if cfg!(debug_assertions)
-            && layer.features.iter().any(|f| f.id == Some(id))
-        {
-            log::warn!("Duplicate feature ID ({id}) in layer {:?}", layer.name);
+        if let Err(e) = self.try_set_id(id) {
+            log::warn!("{e} ({id}) in layer {:?}", self.layer.layer.name);
+        }
+    }
+
+    /// Set the feature ID, checking for duplicates.
+    ///
+    /// * `id` Feature ID.
+    ///
+    /// Returns an error if the layer [IdPolicy](enum.IdPolicy.html) is
+    /// `Strict` and another feature in the layer has the same ID.
+    pub fn try_set_id(&mut self, id: u64) -> Result<()> {
+        if self.layer.ids.contains(&id) {
+            if self.layer.id_policy == IdPolicy::Strict {
+                return Err(Error::DuplicateId());
+            }
+            log::warn!(
+                "Duplicate feature ID ({id}) in layer {:?}",
+                self.layer.layer.name
+            );
         }
         self.feature.id = Some(id);
+        Ok(())
     }
 
     /// Get number of tags (count).
@@ -408,6 +1546,61 @@ impl Feature {
         self.add_tag(key, value);
     }
 
+    /// Add an extension field.
+    ///
+    /// * `number` Field number (16 or higher).
+    /// * `data` Field data (length-delimited).
+    ///
+    /// Field 8192 is reserved for measures (see
+    /// [GeomEncoder::add_point_m](struct.GeomEncoder.html#method.add_point_m)),
+    /// and 19000 to 19999 are reserved by protobuf.
+    pub fn add_extension(&mut self, number: u32, data: &[u8]) -> Result<()> {
+        check_extension(number, 16)?;
+        if number == FEATURE_MEASURES {
+            return Err(Error::InvalidExtension());
+        }
+        let index = self.layer.layer.features.len();
+        let fields = self.layer.ext.features.entry(index).or_default();
+        write_field(fields, number, data);
+        Ok(())
+    }
+
+    /// Add an extension field to the value of a tag.
+    ///
+    /// * `key` Key of a tag already added to the feature.
+    /// * `number` Field number (8 or higher).
+    /// * `data` Field data (length-delimited).
+    ///
+    /// Values are shared by all features in the layer with the same tag
+    /// value, so the field applies to each of them.  Fields 19000 to 19999
+    /// are reserved by protobuf.
+    ///
+    /// Returns an error if the feature has no tag with `key`, or the field
+    /// number is invalid.
+    pub fn add_tag_extension(
+        &mut self,
+        key: &str,
+        number: u32,
+        data: &[u8],
+    ) -> Result<()> {
+        check_extension(number, 8)?;
+        let kidx = *self
+            .layer
+            .key_indices
+            .get(key)
+            .ok_or(Error::InvalidExtension())? as u32;
+        let vidx = self
+            .feature
+            .tags
+            .chunks_exact(2)
+            .rfind(|kv| kv[0] == kidx)
+            .ok_or(Error::InvalidExtension())?[1] as usize;
+        let mut fields = Vec::new();
+        write_field(&mut fields, number, data);
+        self.value_ext.push((vidx, fields));
+        Ok(())
+    }
+
     /// Add a tag.
     fn add_tag(&mut self, key: &str, value: Value) {
         let kidx = self.layer.key_pos(key);
@@ -416,3 +1609,532 @@ impl Feature {
         self.feature.tags.push(vidx as u32);
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::decoder::decode_paths;
+
+    fn linestring_tile() -> Tile {
+        let mut tile = Tile::new(4096);
+        let layer = tile.create_layer("lines");
+        let geom_data = GeomEncoder::new(GeomType::Linestring)
+            .point(0.0, 1024.0)
+            .unwrap()
+            .point(4096.0, 1024.0)
+            .unwrap()
+            .encode()
+            .unwrap();
+        let mut feature = layer.into_feature(geom_data);
+        feature.set_id(7);
+        feature.add_tag_string("name", "main");
+        tile.add_layer(feature.into_layer()).unwrap();
+        Tile::from_bytes(&tile.to_bytes().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_overzoom() {
+        let tile = linestring_tile().overzoom(&[1], 64).unwrap();
+        let layer = &tile.vec_tile.layers[0];
+        assert_eq!(layer.keys, vec!["name".to_string()]);
+        let feature = &layer.features[0];
+        assert_eq!(feature.id, Some(7));
+        assert_eq!(feature.tags, vec![0, 0]);
+        let paths =
+            decode_paths(GeomType::Linestring, &feature.geometry).unwrap();
+        assert_eq!(paths, vec![vec![(-64, 2048), (4096, 2048)]]);
+    }
+
+    #[test]
+    fn test_overzoom_clipped() {
+        let tile = linestring_tile().overzoom(&[2, 0], 0).unwrap();
+        assert_eq!(tile.vec_tile.layers[0].features.len(), 0);
+    }
+
+    #[test]
+    fn test_overzoom_closing_segment() {
+        let mut tile = Tile::new(4096);
+        let layer = tile.create_layer("triangle");
+        // hypotenuse is the closing segment
+        let geom_data = GeomEncoder::new(GeomType::Polygon)
+            .point(0.0, 4096.0)
+            .unwrap()
+            .point(0.0, 0.0)
+            .unwrap()
+            .point(4096.0, 0.0)
+            .unwrap()
+            .encode()
+            .unwrap();
+        tile.add_layer(layer.into_feature(geom_data).into_layer())
+            .unwrap();
+        let child = tile.overzoom(&[0], 0).unwrap();
+        let paths = decode_paths(
+            GeomType::Polygon,
+            &child.vec_tile.layers[0].features[0].geometry,
+        )
+        .unwrap();
+        assert_eq!(
+            paths,
+            vec![vec![(0, 4096), (0, 0), (4096, 0), (4096, 4096)]]
+        );
+        let child = tile.overzoom(&[3], 0).unwrap();
+        assert_eq!(child.vec_tile.layers[0].features.len(), 0);
+    }
+
+    #[test]
+    fn test_with_extent() {
+        let mut tile = linestring_tile().with_extent(512).unwrap();
+        assert_eq!(tile.extent(), 512);
+        let layer = &tile.vec_tile.layers[0];
+        assert_eq!(layer.extent, Some(512));
+        let paths =
+            decode_paths(GeomType::Linestring, &layer.features[0].geometry)
+                .unwrap();
+        assert_eq!(paths, vec![vec![(0, 128), (512, 128)]]);
+        let layer = tile.create_layer("other");
+        assert!(tile.add_layer(layer).is_ok());
+    }
+
+    #[test]
+    fn test_mixed_extent() {
+        let mut tile = Tile::new(4096);
+        let layer = tile.create_layer_with_extent("background", 512);
+        assert!(tile.add_layer(layer).is_err());
+        let mut tile = Tile::new_mixed_extent(4096);
+        let layer = tile.create_layer_with_extent("background", 512);
+        tile.add_layer(layer).unwrap();
+        let layer = tile.create_layer("detail");
+        tile.add_layer(layer).unwrap();
+        let tile = Tile::from_bytes(&tile.to_bytes().unwrap()).unwrap();
+        assert!(tile.is_mixed_extent());
+        assert_eq!(tile.extent(), 512);
+    }
+
+    #[test]
+    fn test_encoded_layer() {
+        let mut other = Tile::new(4096);
+        let layer = add_point(other.create_layer("roads"), Some(1), "a");
+        other.add_layer(layer).unwrap();
+        let data = other.to_bytes().unwrap();
+        let mut tile = Tile::new(4096);
+        let layer = add_point(tile.create_layer("points"), Some(1), "b");
+        tile.add_layer(layer).unwrap();
+        tile.add_encoded_layer(&data).unwrap();
+        assert_eq!(tile.num_layers(), 2);
+        assert!(matches!(
+            tile.add_encoded_layer(&data),
+            Err(Error::DuplicateName())
+        ));
+        assert!(matches!(
+            tile.add_layer(tile.create_layer("roads")),
+            Err(Error::DuplicateName())
+        ));
+        assert!(matches!(
+            Tile::new(512).add_encoded_layer(&data),
+            Err(Error::WrongExtent())
+        ));
+        assert!(tile.add_encoded_layer(&[0x1a, 0x05]).is_err());
+        let bytes = tile.to_bytes().unwrap();
+        assert_eq!(bytes.len(), tile.compute_size());
+        let tile = Tile::from_bytes(&bytes).unwrap();
+        assert_eq!(tile.vec_tile.layers.len(), 2);
+        assert_eq!(tile.vec_tile.layers[1].name, "roads");
+    }
+
+    #[test]
+    fn test_features() {
+        let mut tile = Tile::new(4096);
+        let layer = add_point(tile.create_layer("points"), Some(5), "a");
+        tile.add_layer(layer).unwrap();
+        let tile = Tile::from_bytes(&tile.to_bytes().unwrap()).unwrap();
+        assert_eq!(tile.features("lines").count(), 0);
+        let feature = tile.features("points").next().unwrap();
+        assert_eq!(feature.id(), Some(5));
+        assert_eq!(feature.geom_type(), Some(GeomType::Point));
+        assert_eq!(feature.num_tags(), 1);
+    }
+
+    #[test]
+    fn test_extensions() {
+        let mut tile = Tile::new(4096);
+        let mut layer = add_point(tile.create_layer("points"), Some(1), "a");
+        layer.add_extension(20, b"layer").unwrap();
+        assert!(layer.add_extension(15, b"").is_err());
+        assert!(layer.add_extension(8192, b"").is_err());
+        tile.add_layer(layer).unwrap();
+        tile.add_extension(16, b"tile").unwrap();
+        assert!(tile.add_extension(8192, b"").is_err());
+        let bytes = tile.to_bytes().unwrap();
+        assert_eq!(bytes.len(), tile.compute_size());
+        let tile = Tile::from_bytes(&bytes).unwrap();
+        assert_eq!(tile.extensions(), b"\x82\x01\x04tile");
+        assert_eq!(tile.layer_extensions("points"), b"\xa2\x01\x05layer");
+        assert_eq!(tile.to_bytes().unwrap(), bytes);
+        let tile = tile.with_extent(512).unwrap();
+        assert_eq!(tile.layer_extensions("points"), b"\xa2\x01\x05layer");
+    }
+
+    #[test]
+    fn test_feature_extensions() {
+        let mut tile = Tile::new(4096);
+        let mut layer = tile.create_layer("points");
+        for (i, x) in [1.0, 3000.0, 2.0].into_iter().enumerate() {
+            let geom_data = GeomEncoder::new(GeomType::Point)
+                .point(x, x)
+                .unwrap()
+                .encode()
+                .unwrap();
+            let mut feature = layer.into_feature(geom_data);
+            feature.add_tag_string("name", &format!("{i}"));
+            assert!(feature.add_tag_extension("kind", 8, b"").is_err());
+            assert!(feature.add_tag_extension("name", 7, b"").is_err());
+            assert!(feature.add_extension(15, b"").is_err());
+            assert!(feature.add_extension(8192, b"").is_err());
+            feature
+                .add_extension(30, format!("f{i}").as_bytes())
+                .unwrap();
+            feature
+                .add_tag_extension("name", 8, format!("v{i}").as_bytes())
+                .unwrap();
+            layer = feature.into_layer();
+        }
+        // abandoned feature
+        let geom_data = GeomEncoder::new(GeomType::Point)
+            .point(5.0, 5.0)
+            .unwrap()
+            .encode()
+            .unwrap();
+        let mut feature = layer.into_feature(geom_data);
+        feature.add_tag_string("name", "0");
+        feature.add_extension(30, b"x").unwrap();
+        feature.add_tag_extension("name", 8, b"x").unwrap();
+        layer = feature.layer();
+        tile.add_layer(layer).unwrap();
+        let tile = Tile::from_bytes(&tile.to_bytes().unwrap()).unwrap();
+        let features: Vec<_> = tile.features("points").collect();
+        assert_eq!(features.len(), 3);
+        assert_eq!(features[1].extensions(), b"\xf2\x01\x02f1");
+        assert_eq!(features[1].tag_extensions("name"), b"\x42\x02v1");
+        assert_eq!(features[0].tag_extensions("name"), b"\x42\x02v0");
+        assert_eq!(features[0].tag_extensions("kind"), b"");
+        // second feature is clipped, so the third is re-keyed
+        let child = tile.overzoom(&[0], 0).unwrap();
+        let features: Vec<_> = child.features("points").collect();
+        assert_eq!(features.len(), 2);
+        assert_eq!(features[0].extensions(), b"\xf2\x01\x02f0");
+        assert_eq!(features[1].extensions(), b"\xf2\x01\x02f2");
+        assert_eq!(features[1].tag_extensions("name"), b"\x42\x02v2");
+        let child = tile.with_extent(512).unwrap();
+        let features: Vec<_> = child.features("points").collect();
+        assert_eq!(features[2].extensions(), b"\xf2\x01\x02f2");
+    }
+
+    #[test]
+    fn test_transform_measures() {
+        let mut tile = Tile::new(4096);
+        let layer = tile.create_layer("tracks");
+        let geom_data = GeomEncoder::new(GeomType::Linestring)
+            .point_m(0.0, 0.0, 1.0)
+            .unwrap()
+            .point_m(4096.0, 0.0, 2.0)
+            .unwrap()
+            .encode()
+            .unwrap();
+        tile.add_layer(layer.into_feature(geom_data).into_layer())
+            .unwrap();
+        let tile = Tile::from_bytes(&tile.to_bytes().unwrap()).unwrap();
+        assert!(matches!(
+            tile.overzoom(&[0], 0),
+            Err(Error::FeatureExtension())
+        ));
+        assert!(matches!(
+            tile.with_extent(512),
+            Err(Error::FeatureExtension())
+        ));
+    }
+
+    fn add_point(layer: Layer, id: Option<u64>, name: &str) -> Layer {
+        let geom_data = GeomEncoder::new(GeomType::Point)
+            .point(1.0, 1.0)
+            .unwrap()
+            .encode()
+            .unwrap();
+        let mut feature = layer.into_feature(geom_data);
+        if let Some(id) = id {
+            feature.set_id(id);
+        }
+        feature.add_tag_string("name", name);
+        feature.into_layer()
+    }
+
+    fn feature_ids(layer: &Layer) -> Vec<Option<u64>> {
+        layer.layer.features.iter().map(|f| f.id).collect()
+    }
+
+    #[test]
+    fn test_id_auto_increment() {
+        let tile = Tile::new(4096);
+        let layer = tile
+            .create_layer("points")
+            .id_policy(IdPolicy::AutoIncrement);
+        let layer = add_point(layer, Some(2), "a");
+        let layer = add_point(layer, None, "b");
+        let layer = add_point(layer, None, "c");
+        assert_eq!(feature_ids(&layer), vec![Some(2), Some(1), Some(3)]);
+    }
+
+    #[test]
+    fn test_id_hash_tag() {
+        let tile = Tile::new(4096);
+        let layer = tile
+            .create_layer("points")
+            .id_policy(IdPolicy::HashTag("name".to_string()));
+        let layer = add_point(layer, None, "a");
+        let layer = add_point(layer, None, "b");
+        let layer = add_point(layer, None, "a");
+        let ids = feature_ids(&layer);
+        assert!(ids[0].is_some());
+        assert_ne!(ids[0], ids[1]);
+        assert_eq!(ids[0], ids[2]);
+    }
+
+    #[test]
+    fn test_id_strict() {
+        let tile = Tile::new(4096);
+        let layer = tile.create_layer("points").id_policy(IdPolicy::Strict);
+        let layer = add_point(layer, Some(5), "a");
+        let geom_data = GeomEncoder::new(GeomType::Point)
+            .point(1.0, 1.0)
+            .unwrap()
+            .encode()
+            .unwrap();
+        let mut feature = layer.into_feature(geom_data);
+        assert!(feature.try_set_id(5).is_err());
+        feature.set_id(5);
+        assert_eq!(feature.feature.id, None);
+        assert!(feature.try_set_id(6).is_ok());
+    }
+
+    #[test]
+    fn test_id_hash_collision() {
+        let tile = Tile::new(4096);
+        let mut layer = tile
+            .create_layer("points")
+            .id_policy(IdPolicy::HashTag("name".to_string()));
+        let a = ValueKey::String("a".to_string());
+        layer
+            .hashed_values
+            .insert(a.stable_hash(), ValueKey::String("b".to_string()));
+        let layer = add_point(layer, None, "a");
+        let layer = add_point(layer, None, "b");
+        let ids = feature_ids(&layer);
+        assert_eq!(ids[0], None);
+        assert!(ids[1].is_some());
+    }
+
+    #[test]
+    fn test_optimize() {
+        let tile = Tile::new(4096);
+        let mut layer = tile.create_layer("points");
+        for i in 0..200 {
+            layer = add_point(layer, None, &format!("{i}"));
+        }
+        for _ in 0..100 {
+            layer = add_point(layer, None, "common");
+        }
+        // extension field of "common" value
+        layer.ext.values.insert(200, vec![64, 1]);
+        let saved = layer.optimize();
+        assert!(saved > 0);
+        assert_eq!(layer.ext.values, HashMap::from([(0, vec![64, 1])]));
+        assert_eq!(layer.layer.values.len(), 201);
+        assert_eq!(
+            layer.layer.values[0].string_value.as_deref(),
+            Some("common")
+        );
+        assert_eq!(layer.layer.features[250].tags, vec![0, 0]);
+        assert_eq!(layer.layer.features[0].tags, vec![0, 1]);
+        let layer = add_point(layer, None, "common");
+        assert_eq!(layer.layer.features[300].tags, vec![0, 0]);
+    }
+
+    #[test]
+    fn test_sort_features() {
+        let tile = Tile::new(4096);
+        let mut layer = tile.create_layer("points");
+        for name in ["b", "c", "a"] {
+            layer = add_point(layer, None, name);
+        }
+        let geom_data = GeomEncoder::new(GeomType::Point)
+            .point(1.0, 1.0)
+            .unwrap()
+            .encode()
+            .unwrap();
+        let feature = layer.into_feature(geom_data);
+        let mut layer = feature.into_layer();
+        layer.sort_features(FeatureOrder::Tag("name".to_string()));
+        let tags: Vec<_> = layer
+            .layer
+            .features
+            .iter()
+            .map(|f| f.tags.clone())
+            .collect();
+        assert_eq!(tags, vec![vec![0, 2], vec![0, 0], vec![0, 1], vec![]]);
+    }
+
+    #[test]
+    fn test_sort_features_nan() {
+        let tile = Tile::new(4096);
+        let mut layer = tile.create_layer("points");
+        let vals = [f64::NAN, 2.0, -f64::INFINITY, f64::NAN, 1.0];
+        for (id, val) in vals.into_iter().enumerate() {
+            let geom_data = GeomEncoder::new(GeomType::Point)
+                .point(1.0, 1.0)
+                .unwrap()
+                .encode()
+                .unwrap();
+            let mut feature = layer.into_feature(geom_data);
+            feature.set_id(id as u64);
+            feature.add_tag_double("val", val);
+            layer = feature.into_layer();
+        }
+        layer.sort_features(FeatureOrder::Tag("val".to_string()));
+        assert_eq!(
+            feature_ids(&layer),
+            vec![Some(2), Some(4), Some(1), Some(0), Some(3)]
+        );
+    }
+
+    #[test]
+    fn test_hilbert_distance() {
+        let d: Vec<_> = [(0, 0), (1, 0), (1, 1), (0, 1), (2, 0), (3, 3)]
+            .iter()
+            .map(|(x, y)| hilbert_distance(4, *x, *y))
+            .collect();
+        assert_eq!(d, vec![0, 1, 2, 3, 14, 10]);
+    }
+
+    fn add_line(layer: Layer, pts: &[(f64, f64)], name: &str) -> Layer {
+        let mut enc = GeomEncoder::new(GeomType::Linestring);
+        for (x, y) in pts {
+            enc.add_point(*x, *y).unwrap();
+        }
+        let mut feature = layer.into_feature(enc.encode().unwrap());
+        feature.add_tag_string("name", name);
+        feature.into_layer()
+    }
+
+    #[test]
+    fn test_merge_features() {
+        let tile = Tile::new(4096);
+        let layer = tile.create_layer("lines");
+        let layer = add_line(layer, &[(5.0, 0.0), (10.0, 0.0)], "a");
+        let layer = add_line(layer, &[(0.0, 0.0), (5.0, 0.0)], "a");
+        let layer = add_line(layer, &[(0.0, 5.0), (5.0, 5.0)], "b");
+        let mut layer = add_line(layer, &[(20.0, 0.0), (30.0, 0.0)], "a");
+        layer.merge_features(false).unwrap();
+        assert_eq!(layer.num_features(), 2);
+        let paths = decode_paths(
+            GeomType::Linestring,
+            &layer.layer.features[0].geometry,
+        )
+        .unwrap();
+        assert_eq!(paths.len(), 3);
+        layer.merge_features(true).unwrap();
+        let paths = decode_paths(
+            GeomType::Linestring,
+            &layer.layer.features[0].geometry,
+        )
+        .unwrap();
+        assert_eq!(
+            paths,
+            vec![vec![(0, 0), (5, 0), (10, 0)], vec![(20, 0), (30, 0)]]
+        );
+    }
+
+    #[test]
+    fn test_feature_ext() {
+        let tile = Tile::new(4096);
+        let mut layer = tile.create_layer("tracks");
+        for (m, name) in [(1.0, "b"), (2.0, "c"), (3.0, "a")] {
+            let geom_data = GeomEncoder::new(GeomType::Linestring)
+                .point_m(0.0, 0.0, m)
+                .unwrap()
+                .point_m(5.0, 0.0, m)
+                .unwrap()
+                .encode()
+                .unwrap();
+            let mut feature = layer.into_feature(geom_data);
+            feature.add_tag_string("name", name);
+            layer = feature.into_layer();
+        }
+        let mut layer = add_line(layer, &[(0.0, 5.0), (5.0, 5.0)], "a");
+        let before = layer.ext.features.clone();
+        layer.sort_features(FeatureOrder::Tag("name".to_string()));
+        let ext = &layer.ext.features;
+        assert_eq!(ext.len(), 3);
+        assert_eq!(ext[&0], before[&2]);
+        assert!(!ext.contains_key(&1));
+        assert_eq!(ext[&2], before[&0]);
+        assert_eq!(ext[&3], before[&1]);
+        assert!(matches!(
+            layer.merge_features(false),
+            Err(Error::FeatureExtension())
+        ));
+        assert_eq!(layer.num_features(), 4);
+        assert_eq!(layer.optimize(), 0);
+        // abandoned feature
+        let geom_data = GeomEncoder::new(GeomType::Point)
+            .point_m(1.0, 1.0, 5.0)
+            .unwrap()
+            .encode()
+            .unwrap();
+        let layer = layer.into_feature(geom_data).layer();
+        assert_eq!(layer.ext.features.len(), 3);
+    }
+
+    #[test]
+    fn test_overzoom_invalid() {
+        assert!(linestring_tile().overzoom(&[4], 0).is_err());
+    }
+
+    #[test]
+    fn test_version_1() {
+        let mut tile = Tile::new(4096);
+        let layer = tile.create_layer_v1("legacy");
+        assert_eq!(layer.layer.version, 1);
+        // clockwise exterior ring, with a trailing ClosePath count of 2
+        let mut geom_data = GeomEncoder::new(GeomType::Polygon)
+            .version(1)
+            .unwrap()
+            .point(0.0, 0.0)
+            .unwrap()
+            .point(0.0, 2048.0)
+            .unwrap()
+            .point(2048.0, 2048.0)
+            .unwrap()
+            .encode()
+            .unwrap()
+            .into_vec();
+        *geom_data.last_mut().unwrap() = 23;
+        let feature = VtFeature {
+            r#type: Some(VtGeomType::Polygon as i32),
+            geometry: geom_data,
+            ..Default::default()
+        };
+        let mut layer = layer;
+        layer.layer.features.push(feature);
+        tile.add_layer(layer).unwrap();
+        let tile = Tile::from_bytes(&tile.to_bytes().unwrap()).unwrap();
+        let child = tile.overzoom(&[0], 0).unwrap();
+        let layer = &child.vec_tile.layers[0];
+        assert_eq!(layer.version, 1);
+        let paths = decode_paths_version(
+            GeomType::Polygon,
+            1,
+            &layer.features[0].geometry,
+        )
+        .unwrap();
+        assert_eq!(paths, vec![vec![(0, 0), (0, 4096), (4096, 4096), (0, 0)]]);
+    }
+}
@@ -0,0 +1,645 @@
+// wkb.rs
+//
+// Copyright (c) 2019-2026  Minnesota Department of Transportation
+//
+//! Well-known binary (WKB) and well-known text (WKT) geometry input.
+//!
+use crate::encoder::{GeomData, GeomEncoder, GeomType};
+use crate::error::{Error, Result};
+use pointy::Float;
+
+/// Maximum nesting depth of geometry collections
+const MAX_DEPTH: usize = 32;
+
+/// Simple feature geometry
+#[derive(Debug, PartialEq)]
+enum Geometry {
+    Point(Option<(f64, f64)>),
+    LineString(Vec<(f64, f64)>),
+    Polygon(Vec<Vec<(f64, f64)>>),
+    MultiPoint(Vec<(f64, f64)>),
+    MultiLineString(Vec<Vec<(f64, f64)>>),
+    MultiPolygon(Vec<Vec<Vec<(f64, f64)>>>),
+    Collection(Vec<Geometry>),
+}
+
+/// Reader for WKB / EWKB data
+struct WkbReader<'a> {
+    /// WKB data
+    data: &'a [u8],
+
+    /// Offset of next byte
+    offset: usize,
+
+    /// Little endian byte order
+    little_endian: bool,
+
+    /// Nesting depth of multi-geometries / collections
+    depth: usize,
+}
+
+impl<'a> WkbReader<'a> {
+    /// Create a new WKB reader
+    fn new(data: &'a [u8]) -> Self {
+        WkbReader {
+            data,
+            offset: 0,
+            little_endian: true,
+            depth: 0,
+        }
+    }
+
+    /// Read an array of bytes
+    fn read_bytes<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let end = self.offset + N;
+        let bytes =
+            self.data.get(self.offset..end).ok_or(Error::InvalidWkb())?;
+        self.offset = end;
+        let mut buf = [0; N];
+        buf.copy_from_slice(bytes);
+        Ok(buf)
+    }
+
+    /// Read a u32 value
+    fn read_u32(&mut self) -> Result<u32> {
+        let buf = self.read_bytes()?;
+        Ok(if self.little_endian {
+            u32::from_le_bytes(buf)
+        } else {
+            u32::from_be_bytes(buf)
+        })
+    }
+
+    /// Read an f64 value
+    fn read_f64(&mut self) -> Result<f64> {
+        let buf = self.read_bytes()?;
+        Ok(if self.little_endian {
+            f64::from_le_bytes(buf)
+        } else {
+            f64::from_be_bytes(buf)
+        })
+    }
+
+    /// Read a coördinate, skipping any Z / M values
+    fn read_coord(&mut self, dims: usize) -> Result<(f64, f64)> {
+        let x = self.read_f64()?;
+        let y = self.read_f64()?;
+        for _ in 2..dims {
+            self.read_f64()?;
+        }
+        Ok((x, y))
+    }
+
+    /// Read a count-prefixed list of coördinates
+    fn read_coords(&mut self, dims: usize) -> Result<Vec<(f64, f64)>> {
+        let count = self.read_u32()?;
+        (0..count).map(|_| self.read_coord(dims)).collect()
+    }
+
+    /// Read a count-prefixed list of rings
+    fn read_rings(&mut self, dims: usize) -> Result<Vec<Vec<(f64, f64)>>> {
+        let count = self.read_u32()?;
+        (0..count).map(|_| self.read_coords(dims)).collect()
+    }
+
+    /// Read a count-prefixed list of geometries
+    fn read_geometries(&mut self) -> Result<Vec<Geometry>> {
+        if self.depth >= MAX_DEPTH {
+            return Err(Error::InvalidWkb());
+        }
+        self.depth += 1;
+        let count = self.read_u32()?;
+        let geoms = (0..count).map(|_| self.read_geometry()).collect();
+        self.depth -= 1;
+        geoms
+    }
+
+    /// Read a geometry
+    fn read_geometry(&mut self) -> Result<Geometry> {
+        self.little_endian = match self.read_bytes::<1>()?[0] {
+            0 => false,
+            1 => true,
+            _ => return Err(Error::InvalidWkb()),
+        };
+        let code = self.read_u32()?;
+        // EWKB flags
+        let mut dims = 2;
+        if code & 0x8000_0000 != 0 {
+            dims += 1;
+        }
+        if code & 0x4000_0000 != 0 {
+            dims += 1;
+        }
+        if code & 0x2000_0000 != 0 {
+            let _srid = self.read_u32()?;
+        }
+        // ISO WKB dimensions
+        let code = code & 0x0FFF_FFFF;
+        dims += match code / 1000 {
+            0 => 0,
+            1 | 2 => 1,
+            3 => 2,
+            _ => return Err(Error::InvalidWkb()),
+        };
+        Ok(match code % 1000 {
+            1 => {
+                let (x, y) = self.read_coord(dims)?;
+                if x.is_nan() && y.is_nan() {
+                    Geometry::Point(None)
+                } else {
+                    Geometry::Point(Some((x, y)))
+                }
+            }
+            2 => Geometry::LineString(self.read_coords(dims)?),
+            3 => Geometry::Polygon(self.read_rings(dims)?),
+            4 => Geometry::MultiPoint(points(self.read_geometries()?)?),
+            5 => Geometry::MultiLineString(
+                self.read_geometries()?
+                    .into_iter()
+                    .map(|g| match g {
+                        Geometry::LineString(pts) => Ok(pts),
+                        _ => Err(Error::InvalidWkb()),
+                    })
+                    .collect::<Result<_>>()?,
+            ),
+            6 => Geometry::MultiPolygon(
+                self.read_geometries()?
+                    .into_iter()
+                    .map(|g| match g {
+                        Geometry::Polygon(rings) => Ok(rings),
+                        _ => Err(Error::InvalidWkb()),
+                    })
+                    .collect::<Result<_>>()?,
+            ),
+            7 => Geometry::Collection(self.read_geometries()?),
+            _ => return Err(Error::InvalidWkb()),
+        })
+    }
+}
+
+/// Get coördinates of point geometries (skipping empty points)
+fn points(geoms: Vec<Geometry>) -> Result<Vec<(f64, f64)>> {
+    let mut pts = Vec::with_capacity(geoms.len());
+    for geom in geoms {
+        match geom {
+            Geometry::Point(Some(pt)) => pts.push(pt),
+            Geometry::Point(None) => (),
+            _ => return Err(Error::InvalidWkb()),
+        }
+    }
+    Ok(pts)
+}
+
+/// WKT token
+#[derive(Clone, Debug, PartialEq)]
+enum Token<'a> {
+    Word(&'a str),
+    Number(f64),
+    Open,
+    Close,
+    Comma,
+}
+
+/// Parser for WKT / EWKT text
+struct WktParser<'a> {
+    /// Tokens
+    tokens: Vec<Token<'a>>,
+
+    /// Offset of next token
+    offset: usize,
+
+    /// Nesting depth of geometry collections
+    depth: usize,
+}
+
+impl<'a> WktParser<'a> {
+    /// Create a new WKT parser
+    fn new(text: &'a str) -> Self {
+        // Skip EWKT SRID prefix
+        let text = match text.split_once(';') {
+            Some((srid, text))
+                if srid.trim().to_ascii_uppercase().starts_with("SRID=") =>
+            {
+                text
+            }
+            _ => text,
+        };
+        let mut tokens = Vec::new();
+        let mut chars = text.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '(' => tokens.push(Token::Open),
+                ')' => tokens.push(Token::Close),
+                ',' => tokens.push(Token::Comma),
+                c if c.is_whitespace() => (),
+                _ => {
+                    let mut end = i + c.len_utf8();
+                    while let Some((j, c)) = chars.peek() {
+                        if c.is_whitespace() || "(),".contains(*c) {
+                            break;
+                        }
+                        end = j + c.len_utf8();
+                        chars.next();
+                    }
+                    let word = &text[i..end];
+                    tokens.push(match word.parse::<f64>() {
+                        Ok(num) if !c.is_alphabetic() => Token::Number(num),
+                        _ => Token::Word(word),
+                    });
+                }
+            }
+        }
+        WktParser {
+            tokens,
+            offset: 0,
+            depth: 0,
+        }
+    }
+
+    /// Peek at the next token
+    fn peek(&self) -> Option<&Token<'a>> {
+        self.tokens.get(self.offset)
+    }
+
+    /// Get the next token
+    fn next_token(&mut self) -> Result<Token<'a>> {
+        let token = self.peek().cloned().ok_or(Error::InvalidWkt())?;
+        self.offset += 1;
+        Ok(token)
+    }
+
+    /// Expect a specific token
+    fn expect(&mut self, token: Token) -> Result<()> {
+        if self.next_token()? == token {
+            Ok(())
+        } else {
+            Err(Error::InvalidWkt())
+        }
+    }
+
+    /// Check for an `EMPTY` keyword
+    fn is_empty(&mut self) -> bool {
+        match self.peek() {
+            Some(Token::Word(w)) if w.eq_ignore_ascii_case("EMPTY") => {
+                self.offset += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Parse a list of items, enclosed in parentheses
+    fn parse_list<T>(
+        &mut self,
+        mut item: impl FnMut(&mut Self) -> Result<T>,
+    ) -> Result<Vec<T>> {
+        if self.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.expect(Token::Open)?;
+        let mut items = vec![item(self)?];
+        loop {
+            match self.next_token()? {
+                Token::Comma => items.push(item(self)?),
+                Token::Close => return Ok(items),
+                _ => return Err(Error::InvalidWkt()),
+            }
+        }
+    }
+
+    /// Parse a coördinate, skipping any Z / M values
+    fn parse_coord(&mut self) -> Result<(f64, f64)> {
+        let (Token::Number(x), Token::Number(y)) =
+            (self.next_token()?, self.next_token()?)
+        else {
+            return Err(Error::InvalidWkt());
+        };
+        while let Some(Token::Number(_)) = self.peek() {
+            self.offset += 1;
+        }
+        Ok((x, y))
+    }
+
+    /// Parse a list of coördinates
+    fn parse_coords(&mut self) -> Result<Vec<(f64, f64)>> {
+        self.parse_list(Self::parse_coord)
+    }
+
+    /// Parse a list of rings
+    fn parse_rings(&mut self) -> Result<Vec<Vec<(f64, f64)>>> {
+        self.parse_list(Self::parse_coords)
+    }
+
+    /// Parse a multipoint member, with optional parentheses
+    fn parse_multipoint_coord(&mut self) -> Result<(f64, f64)> {
+        if self.peek() == Some(&Token::Open) {
+            self.offset += 1;
+            let pt = self.parse_coord()?;
+            self.expect(Token::Close)?;
+            Ok(pt)
+        } else {
+            self.parse_coord()
+        }
+    }
+
+    /// Parse a geometry
+    fn parse_geometry(&mut self) -> Result<Geometry> {
+        let Token::Word(tp) = self.next_token()? else {
+            return Err(Error::InvalidWkt());
+        };
+        let tp = tp.to_ascii_uppercase();
+        // Skip dimension keywords
+        if let Some(Token::Word(w)) = self.peek()
+            && ["Z", "M", "ZM"].iter().any(|d| w.eq_ignore_ascii_case(d))
+        {
+            self.offset += 1;
+        }
+        // Dimension suffixes can also be attached to the type
+        let tp = tp.trim_end_matches(['Z', 'M']);
+        Ok(match tp {
+            "POINT" => {
+                Geometry::Point(self.parse_list(Self::parse_coord)?.pop())
+            }
+            "LINESTRING" => Geometry::LineString(self.parse_coords()?),
+            "POLYGON" => Geometry::Polygon(self.parse_rings()?),
+            "MULTIPOINT" => Geometry::MultiPoint(
+                self.parse_list(Self::parse_multipoint_coord)?,
+            ),
+            "MULTILINESTRING" => Geometry::MultiLineString(self.parse_rings()?),
+            "MULTIPOLYGON" => {
+                Geometry::MultiPolygon(self.parse_list(Self::parse_rings)?)
+            }
+            "GEOMETRYCOLLECTION" => {
+                if self.depth >= MAX_DEPTH {
+                    return Err(Error::InvalidWkt());
+                }
+                self.depth += 1;
+                let geoms = self.parse_list(Self::parse_geometry)?;
+                self.depth -= 1;
+                Geometry::Collection(geoms)
+            }
+            _ => return Err(Error::InvalidWkt()),
+        })
+    }
+
+    /// Parse complete text
+    fn parse(mut self) -> Result<Geometry> {
+        let geom = self.parse_geometry()?;
+        if self.peek().is_some() {
+            return Err(Error::InvalidWkt());
+        }
+        Ok(geom)
+    }
+}
+
+/// Encoders for each geometry type
+struct Encoders<F>
+where
+    F: Float,
+{
+    points: GeomEncoder<F>,
+    linestrings: GeomEncoder<F>,
+    polygons: GeomEncoder<F>,
+}
+
+impl<F> Encoders<F>
+where
+    F: Float,
+{
+    /// Create encoders from a template encoder
+    fn new(enc: &GeomEncoder<F>) -> Self {
+        Encoders {
+            points: enc.with_geom_type(GeomType::Point),
+            linestrings: enc.with_geom_type(GeomType::Linestring),
+            polygons: enc.with_geom_type(GeomType::Polygon),
+        }
+    }
+
+    /// Convert a coördinate
+    fn coord((x, y): (f64, f64)) -> Result<(F, F)> {
+        Ok((
+            F::from(x).ok_or(Error::InvalidValue())?,
+            F::from(y).ok_or(Error::InvalidValue())?,
+        ))
+    }
+
+    /// Add a point
+    fn add_point(&mut self, pt: (f64, f64)) -> Result<()> {
+        let (x, y) = Self::coord(pt)?;
+        self.points.add_point(x, y)
+    }
+
+    /// Add a linestring
+    fn add_linestring(&mut self, pts: &[(f64, f64)]) -> Result<()> {
+        for pt in pts {
+            let (x, y) = Self::coord(*pt)?;
+            self.linestrings.add_point(x, y)?;
+        }
+        self.linestrings.complete_geom()
+    }
+
+    /// Add a polygon
+    fn add_polygon(&mut self, rings: &[Vec<(f64, f64)>]) -> Result<()> {
+        for (i, ring) in rings.iter().enumerate() {
+            let ring = ring
+                .iter()
+                .map(|pt| Self::coord(*pt))
+                .collect::<Result<Vec<_>>>()?;
+            self.polygons.add_ring(&ring, i == 0)?;
+        }
+        Ok(())
+    }
+
+    /// Add a geometry
+    fn add_geometry(&mut self, geom: &Geometry) -> Result<()> {
+        match geom {
+            Geometry::Point(Some(pt)) => self.add_point(*pt)?,
+            Geometry::Point(None) => (),
+            Geometry::LineString(pts) => self.add_linestring(pts)?,
+            Geometry::Polygon(rings) => self.add_polygon(rings)?,
+            Geometry::MultiPoint(pts) => {
+                for pt in pts {
+                    self.add_point(*pt)?;
+                }
+            }
+            Geometry::MultiLineString(lines) => {
+                for pts in lines {
+                    self.add_linestring(pts)?;
+                }
+            }
+            Geometry::MultiPolygon(polygons) => {
+                for rings in polygons {
+                    self.add_polygon(rings)?;
+                }
+            }
+            Geometry::Collection(geoms) => {
+                for geom in geoms {
+                    self.add_geometry(geom)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Encode all non-empty geometry
+    fn encode(self) -> Result<Vec<GeomData>> {
+        let mut geoms = Vec::new();
+        for enc in [self.points, self.linestrings, self.polygons] {
+            let geom_data = enc.encode()?;
+            if !geom_data.is_empty() {
+                geoms.push(geom_data);
+            }
+        }
+        Ok(geoms)
+    }
+}
+
+impl<F> GeomEncoder<F>
+where
+    F: Float,
+{
+    /// Encode a geometry, consuming the encoder.
+    fn encode_geometry(self, geom: &Geometry) -> Result<Vec<GeomData>> {
+        let mut encoders = Encoders::new(&self);
+        encoders.add_geometry(geom)?;
+        encoders.encode()
+    }
+
+    /// Encode WKB (or EWKB) geometry, consuming the encoder.
+    ///
+    /// * `wkb` WKB data.
+    ///
+    /// The encoder's transform, bounding box and other settings are applied,
+    /// but its geometry type is ignored.  Instead, one `GeomData` is returned
+    /// for each geometry type present, in order: points, linestrings, then
+    /// polygons.  This allows a GeometryCollection to be split into separate
+    /// features.  Z and M values are ignored, and polygon winding order is
+    /// corrected.
+    ///
+    /// # Example
+    /// ```
+    /// # use mvt::{Error, GeomEncoder, GeomType};
+    /// # fn main() -> Result<(), Error> {
+    /// let wkb = [
+    ///     1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 36, 64, 0, 0, 0, 0, 0, 0, 52, 64,
+    /// ];
+    /// let geoms = GeomEncoder::<f64>::new(GeomType::Point).encode_wkb(&wkb)?;
+    /// assert_eq!(geoms.len(), 1);
+    /// # Ok(()) }
+    /// ```
+    pub fn encode_wkb(self, wkb: &[u8]) -> Result<Vec<GeomData>> {
+        let mut reader = WkbReader::new(wkb);
+        let geom = reader.read_geometry()?;
+        self.encode_geometry(&geom)
+    }
+
+    /// Encode WKT (or EWKT) geometry, consuming the encoder.
+    ///
+    /// * `wkt` WKT text.
+    ///
+    /// Settings and results are the same as for
+    /// [encode_wkb](#method.encode_wkb).
+    ///
+    /// # Example
+    /// ```
+    /// # use mvt::{Error, GeomEncoder, GeomType};
+    /// # fn main() -> Result<(), Error> {
+    /// let geoms = GeomEncoder::<f64>::new(GeomType::Point).encode_wkt(
+    ///     "GEOMETRYCOLLECTION(POINT(1 2), LINESTRING(0 0, 10 10))",
+    /// )?;
+    /// assert_eq!(geoms.len(), 2);
+    /// # Ok(()) }
+    /// ```
+    pub fn encode_wkt(self, wkt: &str) -> Result<Vec<GeomData>> {
+        let geom = WktParser::new(wkt).parse()?;
+        self.encode_geometry(&geom)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn wkt(text: &str) -> Geometry {
+        WktParser::new(text).parse().unwrap()
+    }
+
+    #[test]
+    fn test_wkt() {
+        assert_eq!(wkt("POINT (1 2)"), Geometry::Point(Some((1.0, 2.0))));
+        assert_eq!(wkt("point z (1 2 3)"), Geometry::Point(Some((1.0, 2.0))));
+        assert_eq!(wkt("POINT EMPTY"), Geometry::Point(None));
+        assert_eq!(
+            wkt("SRID=4326;MULTIPOINT ((1 2), 3 4)"),
+            Geometry::MultiPoint(vec![(1.0, 2.0), (3.0, 4.0)])
+        );
+        assert_eq!(
+            wkt("MULTIPOLYGON (((0 0, 1 0, 1 1, 0 0)))"),
+            Geometry::MultiPolygon(vec![vec![vec![
+                (0.0, 0.0),
+                (1.0, 0.0),
+                (1.0, 1.0),
+                (0.0, 0.0)
+            ]]])
+        );
+        assert!(WktParser::new("POINT (1)").parse().is_err());
+        assert!(WktParser::new("CIRCLE (1 2)").parse().is_err());
+    }
+
+    #[test]
+    fn test_wkb() {
+        // EWKB big endian point with SRID and Z
+        let mut wkb = vec![0, 0xA0, 0, 0, 1, 0, 0, 0x10, 0xE6];
+        for v in [1.0_f64, 2.0, 3.0] {
+            wkb.extend(v.to_be_bytes());
+        }
+        let geom = WkbReader::new(&wkb).read_geometry().unwrap();
+        assert_eq!(geom, Geometry::Point(Some((1.0, 2.0))));
+        assert!(WkbReader::new(&wkb[..20]).read_geometry().is_err());
+    }
+
+    #[test]
+    fn test_depth() {
+        let text = "GEOMETRYCOLLECTION (".repeat(100) + &")".repeat(100);
+        assert!(WktParser::new(&text).parse().is_err());
+        let text =
+            "GEOMETRYCOLLECTION (".repeat(10) + "POINT EMPTY" + &")".repeat(10);
+        assert!(WktParser::new(&text).parse().is_ok());
+        let mut wkb = Vec::new();
+        for _ in 0..100_000 {
+            wkb.extend([1, 7, 0, 0, 0, 1, 0, 0, 0]);
+        }
+        assert!(WkbReader::new(&wkb).read_geometry().is_err());
+    }
+
+    #[test]
+    fn test_winding() {
+        // Counter-clockwise exterior ring (in tile coördinates)
+        let geoms = GeomEncoder::<f64>::new(GeomType::Polygon)
+            .encode_wkt("POLYGON ((0 0, 0 10, 10 10, 10 0, 0 0))")
+            .unwrap();
+        assert_eq!(geoms.len(), 1);
+        assert_eq!(geoms[0].ring_areas(), vec![100.0]);
+        // Version 1: original order
+        let geoms = GeomEncoder::<f64>::new(GeomType::Polygon)
+            .version(1)
+            .unwrap()
+            .encode_wkt("POLYGON ((0 0, 0 10, 10 10, 10 0, 0 0))")
+            .unwrap();
+        assert_eq!(geoms[0].ring_areas(), vec![-100.0]);
+    }
+
+    #[test]
+    fn test_ring_clipping() {
+        let bbox = pointy::BBox::new([(0.0, 0.0), (10.0, 10.0)]);
+        // closing segment crosses the right edge
+        let geoms = GeomEncoder::<f64>::new(GeomType::Polygon)
+            .bbox(bbox)
+            .encode_wkt("POLYGON ((5 2, 8 2, 14 8, 5 2))")
+            .unwrap();
+        assert_eq!(
+            geoms[0].paths(),
+            vec![vec![(5, 2), (8, 2), (10, 4), (10, 8), (10, 5)]]
+        );
+    }
+}
@@ -0,0 +1,55 @@
+// cli.rs
+//
+// Copyright (c) 2019-2026  Minnesota Department of Transportation
+//
+use mvt::{GeomEncoder, GeomType, Tile};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Write a tile with one point feature
+fn write_tile(dir: &Path, name: &str, x: f64) -> PathBuf {
+    let mut tile = Tile::new(4096);
+    let layer = tile.create_layer("points");
+    let geom_data = GeomEncoder::new(GeomType::Point)
+        .point(x, 0.0)
+        .unwrap()
+        .encode()
+        .unwrap();
+    let mut feature = layer.into_feature(geom_data);
+    feature.set_id(1);
+    tile.add_layer(feature.into_layer()).unwrap();
+    let path = dir.join(name);
+    std::fs::write(&path, tile.to_bytes().unwrap()).unwrap();
+    path
+}
+
+/// Run `mvt diff` and get the exit code
+fn diff(args: &[&str], a: &Path, b: &Path) -> Option<i32> {
+    Command::new(env!("CARGO_BIN_EXE_mvt"))
+        .arg("diff")
+        .args(args)
+        .arg(a)
+        .arg(b)
+        .output()
+        .unwrap()
+        .status
+        .code()
+}
+
+#[test]
+fn test_diff_exit_code() {
+    let dir = std::env::temp_dir()
+        .join(format!("mvt-cli-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let a = write_tile(&dir, "a.mvt", 0.0);
+    let b = write_tile(&dir, "b.mvt", 10.0);
+    let missing = dir.join("missing.mvt");
+    assert_eq!(diff(&[], &a, &a), Some(0));
+    assert_eq!(diff(&[], &a, &b), Some(1));
+    assert_eq!(diff(&["--tolerance", "10"], &a, &b), Some(0));
+    assert_eq!(diff(&["--tolerance", "-1"], &a, &b), Some(2));
+    assert_eq!(diff(&["--tolerance", "NaN"], &a, &b), Some(2));
+    assert_eq!(diff(&["--tolerance", "inf"], &a, &b), Some(2));
+    assert_eq!(diff(&[], &a, &missing), Some(2));
+    std::fs::remove_dir_all(&dir).unwrap();
+}